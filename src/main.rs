@@ -2,6 +2,11 @@ use clap::{App, Arg};
 
 mod server;
 mod model;
+mod wallet;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use model::{parse_duration, Config, FailurePolicy, SystemClock, Value, NATIVE_ASSET};
 
 fn main() {
     let matches = App::new("b")
@@ -11,6 +16,53 @@ fn main() {
         .subcommand(
             App::new("start-node")
                 .about("Launch the server")
+                .arg(
+                    Arg::with_name("mempool-size")
+                        .long("mempool-size")
+                        .help("Maximum number of pending transactions")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("block-transactions")
+                        .long("block-transactions")
+                        .help("Maximum number of transactions included in a block")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("block-time")
+                        .long("block-time")
+                        .help("Time between two blocks, such as 10s or 500ms [default: 10s]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("skip-empty")
+                        .long("skip-empty")
+                        .help("Do not mint blocks without transactions"),
+                )
+                .arg(
+                    Arg::with_name("block-gas")
+                        .long("block-gas")
+                        .help("Gas budget of a block; transactions that do not fit wait for the next one")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("data-dir")
+                        .long("data-dir")
+                        .help("Directory where blocks are stored and replayed from on startup")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("producer")
+                        .long("producer")
+                        .help("Account credited with the fees of the blocks minted by the node")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("min-fee")
+                        .long("min-fee")
+                        .help("Lowest fee a transfer must offer to enter the mempool")
+                        .takes_value(true),
+                )
         )
         .subcommand(
             App::new("balance")
@@ -21,6 +73,18 @@ fn main() {
                         .takes_value(true)
                         .required(true),
                 )
+                .arg(
+                    Arg::with_name("at")
+                        .long("at")
+                        .help("Height of the block at the end of which to read the balance")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset of the balance [default: b]")
+                        .takes_value(true),
+                )
         )
         .subcommand(
             App::new("create-account")
@@ -36,6 +100,18 @@ fn main() {
                         .help("Starting balance of the account")
                         .takes_value(true)
                         .required(true),
+                )
+                .arg(
+                    Arg::with_name("public-key")
+                        .long("public-key")
+                        .help("Hex encoded Ed25519 public key of the account, instead of a wallet key")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key of the account, generated if missing [default: the account id]")
+                        .takes_value(true),
                 ),
         )
         .subcommand(
@@ -58,30 +134,1139 @@ fn main() {
                         .help("Amount to transfer")
                         .takes_value(true)
                         .required(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset to transfer")
+                        .takes_value(true)
+                        .default_value(NATIVE_ASSET),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transfer, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transfer [default: the sender id]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("Show the receipt and balances the transfer would lead to, without sending it"),
+                ),
+        )
+        .subcommand(
+            App::new("transfer-batch")
+                .about("Send several transfers that all succeed or all fail")
+                .arg(
+                    Arg::with_name("file")
+                        .help("CSV file with one from,to,amount[,asset] row per transfer, all from the same account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer for the whole batch, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the batch, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the batch [default: the sender id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("escrow")
+                .about("Transfer funds that the recipient only receives at a future block")
+                .arg(
+                    Arg::with_name("from-account")
+                        .help("ID of the sender")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("to-account")
+                        .help("ID of the recipient")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .help("Amount to transfer")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("release-height")
+                        .help("Height of the block at the end of which the recipient is credited")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset to transfer")
+                        .takes_value(true)
+                        .default_value(NATIVE_ASSET),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the sender id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("cancel-escrow")
+                .about("Take back the funds of an escrow before they are released")
+                .arg(
+                    Arg::with_name("from-account")
+                        .help("ID of the sender of the escrow")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("escrow")
+                        .help("ID of the escrow, the ID of the transaction that created it")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the sender id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("escrows")
+                .about("List the escrows holding funds")
+                .arg(
+                    Arg::with_name("account")
+                        .long("account")
+                        .help("Only list the escrows sent or received by this account")
+                        .takes_value(true),
+                )
+        )
+        .subcommand(
+            App::new("schedule")
+                .about("Pay an account every few blocks, a given number of times")
+                .arg(
+                    Arg::with_name("from-account")
+                        .help("ID of the sender")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("to-account")
+                        .help("ID of the recipient")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .help("Amount of each payment")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("every")
+                        .long("every")
+                        .help("Number of blocks between payments")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("times")
+                        .long("times")
+                        .help("Number of payments")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("on-failure")
+                        .long("on-failure")
+                        .help("Whether a payment the sender cannot afford is skipped or cancels the schedule")
+                        .possible_values(&["skip", "cancel"])
+                        .default_value("skip"),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset to transfer")
+                        .takes_value(true)
+                        .default_value(NATIVE_ASSET),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer once, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the sender id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("cancel-schedule")
+                .about("Drop the remaining payments of a schedule")
+                .arg(
+                    Arg::with_name("from-account")
+                        .help("ID of the sender of the schedule")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("schedule")
+                        .help("ID of the schedule, the ID of the transaction that created it")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the sender id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("schedules")
+                .about("List the schedules with payments left")
+                .arg(
+                    Arg::with_name("account")
+                        .long("account")
+                        .help("Only list the schedules sent or received by this account")
+                        .takes_value(true),
+                )
+        )
+        .subcommand(
+            App::new("create-multisig")
+                .about("Create an account whose withdrawals need the approval of several members")
+                .arg(
+                    Arg::with_name("creator")
+                        .help("ID of the account creating the multisig account and paying its fee")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("id-of-account")
+                        .help("ID of the account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("threshold")
+                        .help("Number of members that must approve a withdrawal")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("members")
+                        .help("IDs of the member accounts")
+                        .takes_value(true)
+                        .multiple(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the creator id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("propose")
+                .about("Propose a withdrawal from a multisig account, approving it")
+                .arg(
+                    Arg::with_name("member")
+                        .help("ID of the proposing member")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("multisig-account")
+                        .help("ID of the multisig account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("to-account")
+                        .help("ID of the recipient")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .help("Amount to transfer")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset to transfer")
+                        .takes_value(true)
+                        .default_value(NATIVE_ASSET),
+                )
+                .arg(
+                    Arg::with_name("expires-in")
+                        .long("expires-in")
+                        .help("Number of blocks the proposal can be approved for")
+                        .takes_value(true)
+                        .default_value("100"),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer by the proposer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the member id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("approve")
+                .about("Approve a withdrawal proposed on a multisig account")
+                .arg(
+                    Arg::with_name("member")
+                        .help("ID of the approving member")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("multisig-account")
+                        .help("ID of the multisig account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("proposal")
+                        .help("ID of the proposal, the ID of the transaction that proposed it")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the member id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("proposals")
+                .about("List the multisig withdrawals waiting for approvals")
+                .arg(
+                    Arg::with_name("account")
+                        .long("account")
+                        .help("Only list the proposals of this multisig account")
+                        .takes_value(true),
+                )
+        )
+        .subcommand(
+            App::new("allow")
+                .about("Allow a spender account to move funds out of an account, replacing any previous allowance")
+                .arg(
+                    Arg::with_name("owner")
+                        .help("ID of the account whose funds can be spent")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("spender")
+                        .help("ID of the account allowed to spend them")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .help("Amount the spender may move, 0 to revoke the allowance")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset the allowance applies to")
+                        .takes_value(true)
+                        .default_value(NATIVE_ASSET),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer by the owner, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the owner id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("transfer-from")
+                .about("Move funds out of an account, within the allowance it gave the spender")
+                .arg(
+                    Arg::with_name("spender")
+                        .help("ID of the spending account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .help("ID of the account the funds are taken from")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("to-account")
+                        .help("ID of the recipient")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .help("Amount to transfer")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset to transfer")
+                        .takes_value(true)
+                        .default_value(NATIVE_ASSET),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer by the spender, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the spender id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("allowance")
+                .about("Get the amount a spender may still move out of an account")
+                .arg(
+                    Arg::with_name("owner")
+                        .help("ID of the account whose funds can be spent")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("spender")
+                        .help("ID of the spending account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .long("asset")
+                        .help("Asset the allowance applies to")
+                        .takes_value(true)
+                        .default_value(NATIVE_ASSET),
+                )
+        )
+        .subcommand(
+            App::new("contract")
+                .about("Deploy and call contracts, accounts whose funds are moved by their code")
+                .subcommand(
+                    App::new("deploy")
+                        .about("Assemble a contract and deploy it to a new account")
+                        .arg(
+                            Arg::with_name("deployer")
+                                .help("ID of the deploying account")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("contract")
+                                .help("ID of the contract account to create")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("file")
+                                .help("Assembly source of the contract")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("fee")
+                                .long("fee")
+                                .help("Fee paid to the block producer by the deployer, in b")
+                                .takes_value(true)
+                                .default_value("0"),
+                        )
+                        .arg(
+                            Arg::with_name("nonce")
+                                .long("nonce")
+                                .help("Nonce of the transaction, fetched from the node when omitted")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("key")
+                                .long("key")
+                                .help("Name of the wallet key signing the transaction [default: the deployer id]")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    App::new("call")
+                        .about("Run the code of a contract")
+                        .arg(
+                            Arg::with_name("caller")
+                                .help("ID of the calling account")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("contract")
+                                .help("ID of the contract account")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("args")
+                                .help("Arguments of the call, read as integers when they are numbers")
+                                .takes_value(true)
+                                .multiple(true),
+                        )
+                        .arg(
+                            Arg::with_name("value")
+                                .long("value")
+                                .help("Amount of b sent to the contract before its code runs")
+                                .takes_value(true)
+                                .default_value("0"),
+                        )
+                        .arg(
+                            Arg::with_name("gas")
+                                .long("gas")
+                                .help("Most gas the call may use")
+                                .takes_value(true)
+                                .default_value("10000"),
+                        )
+                        .arg(
+                            Arg::with_name("fee")
+                                .long("fee")
+                                .help("Fee paid to the block producer by the caller, in b")
+                                .takes_value(true)
+                                .default_value("0"),
+                        )
+                        .arg(
+                            Arg::with_name("nonce")
+                                .long("nonce")
+                                .help("Nonce of the transaction, fetched from the node when omitted")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("key")
+                                .long("key")
+                                .help("Name of the wallet key signing the transaction [default: the caller id]")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    App::new("show")
+                        .about("Get the code and storage of a contract")
+                        .arg(
+                            Arg::with_name("contract")
+                                .help("ID of the contract account")
+                                .takes_value(true)
+                                .required(true),
+                        ),
                 ),
         )
+        .subcommand(
+            App::new("issue-asset")
+                .about("Create a new asset, crediting its whole supply to the issuer")
+                .arg(
+                    Arg::with_name("issuer")
+                        .help("ID of the issuing account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("asset")
+                        .help("ID of the asset")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("supply")
+                        .help("Total supply of the asset")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .long("fee")
+                        .help("Fee paid to the block producer, in b")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .help("Nonce of the transaction, fetched from the node when omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .help("Name of the wallet key signing the transaction [default: the issuer id]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("assets")
+                .about("List the issued assets")
+        )
+        .subcommand(
+            App::new("supply")
+                .about("Show the amount of each asset in existence")
+        )
+        .subcommand(
+            App::new("wallet")
+                .about("Manage the keys signing your transactions")
+                .subcommand(
+                    App::new("new")
+                        .about("Generate a new key")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the key")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                )
+                .subcommand(
+                    App::new("list")
+                        .about("List the keys and their public keys")
+                )
+                .subcommand(
+                    App::new("import")
+                        .about("Add an existing secret key, read from the terminal or stdin")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the key")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                )
+                .subcommand(
+                    App::new("export")
+                        .about("Print the secret key of a key")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the key")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                )
+                .subcommand(
+                    App::new("show")
+                        .about("Print the public key of a key")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Name of the key")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                )
+        )
+        .subcommand(
+            App::new("verify")
+                .about("Check that every block links to its parent")
+        )
+        .subcommand(
+            App::new("account")
+                .about("Show the balance and nonce of an account")
+                .arg(
+                    Arg::with_name("account")
+                        .help("ID of the account")
+                        .takes_value(true)
+                        .required(true),
+                )
+        )
+        .subcommand(
+            App::new("history")
+                .about("List the transactions that involved an account")
+                .arg(
+                    Arg::with_name("account")
+                        .help("ID of the account")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("offset")
+                        .long("offset")
+                        .help("Number of transactions to skip, oldest first")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .help("Maximum number of transactions to list")
+                        .takes_value(true)
+                        .default_value("20"),
+                )
+        )
+        .subcommand(
+            App::new("block")
+                .about("Show a block")
+                .arg(
+                    Arg::with_name("height")
+                        .help("Height of the block, or `latest`")
+                        .takes_value(true)
+                        .required(true),
+                )
+        )
+        .subcommand(
+            App::new("blocks")
+                .about("List blocks")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .help("Height of the first block to list")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .help("Maximum number of blocks to list")
+                        .takes_value(true)
+                        .default_value("20"),
+                )
+        )
+        .subcommand(
+            App::new("tx-status")
+                .about("Show the receipt of a transaction")
+                .arg(
+                    Arg::with_name("id")
+                        .help("ID of the transaction")
+                        .takes_value(true)
+                        .required(true),
+                )
+        )
+        .subcommand(
+            App::new("mempool")
+                .about("List the transactions waiting for the next block")
+        )
         .get_matches();
 
         match matches.subcommand() {
-            ("start-node", _) => {
-                    server::server_single_thread();
+            ("start-node", Some(sub_matches)) => {
+                    let mut config = Config::default();
+                    if let Some(mempool_size) = sub_matches.value_of("mempool-size") {
+                        config.mempool_size = mempool_size.parse::<usize>().expect("Invalid mempool size");
+                    }
+                    if let Some(block_transactions) = sub_matches.value_of("block-transactions") {
+                        config.block_transactions = block_transactions.parse::<usize>().expect("Invalid block transaction limit");
+                    }
+                    if let Some(block_time) = sub_matches.value_of("block-time") {
+                        config.block_time = parse_duration(block_time).expect("Invalid block time");
+                    }
+                    config.skip_empty = sub_matches.is_present("skip-empty");
+                    if let Some(block_gas) = sub_matches.value_of("block-gas") {
+                        config.block_gas = block_gas.parse::<u64>().expect("Invalid block gas budget");
+                    }
+                    config.data_dir = sub_matches.value_of("data-dir").map(PathBuf::from);
+                    config.producer = sub_matches.value_of("producer").map(str::to_string);
+                    if let Some(min_fee) = sub_matches.value_of("min-fee") {
+                        config.min_fee = min_fee.parse::<u128>().expect("Invalid minimum fee");
+                    }
+                    server::server_single_thread(config, Arc::new(SystemClock));
                 }
             ("balance", Some(sub_matches)) => {
                     let id_of_account = sub_matches.value_of("account").expect("Please provide an account id").to_string();
-                    server::balance(id_of_account);
+                    let asset = sub_matches.value_of("asset").map(str::to_string);
+                    let height = sub_matches.value_of("at").map(|height| height.parse::<u64>().expect("Invalid height"));
+                    server::balance(id_of_account, asset, height);
                 }
             ("create-account", Some(sub_matches)) => {
                 let id_of_account = sub_matches.value_of("id-of-account").expect("Please provide an account id").to_string();
                 let starting_balance = sub_matches.value_of("starting-balance").expect("Please provide an initial balance").parse::<u128>().expect("Invalid starting balance");
                 println!("Creating account with id {} and starting balance {}", id_of_account, starting_balance);
-                server::create_account(id_of_account, starting_balance);
+                let public_key = match sub_matches.value_of("public-key") {
+                    Some(public_key) => public_key.to_string(),
+                    None => wallet::account_public_key(sub_matches.value_of("key").unwrap_or(&id_of_account)),
+                };
+                server::create_account(id_of_account, starting_balance, public_key);
             }
             ("transfer", Some(sub_matches)) => {
                 let from_account = sub_matches.value_of("from-account").expect("Please provide an account id").to_string();
                 let to_account = sub_matches.value_of("to-account").expect("Please provide an account id").to_string();
                 let amount = sub_matches.value_of("amount").expect("Please provide an amount").parse::<u128>().expect("Invalid amount");
-                println!("Transferring {} from {} to {}", amount, from_account, to_account);
-                server::transfer(from_account, to_account, amount);
+                let asset = sub_matches.value_of("asset").unwrap().to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let dry_run = sub_matches.is_present("dry-run");
+                if dry_run {
+                    println!("Simulating a transfer of {}{} from {} to {}", amount, asset, from_account, to_account);
+                } else {
+                    println!("Transferring {}{} from {} to {}", amount, asset, from_account, to_account);
+                }
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&from_account));
+                server::transfer(from_account, to_account, amount, asset, fee, nonce, &secret_key, dry_run);
+            }
+            ("transfer-batch", Some(sub_matches)) => {
+                let file = sub_matches.value_of("file").expect("Please provide a CSV file");
+                let (from_account, legs) = match server::read_batch_file(file) {
+                    Ok(batch) => batch,
+                    Err(err) => {
+                        eprintln!("Invalid batch file: {}", err);
+                        std::process::exit(1);
+                    }
+                };
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                println!("Sending {} transfers from {}", legs.len(), from_account);
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&from_account));
+                server::transfer_batch(from_account, legs, fee, nonce, &secret_key);
+            }
+            ("escrow", Some(sub_matches)) => {
+                let from_account = sub_matches.value_of("from-account").expect("Please provide an account id").to_string();
+                let to_account = sub_matches.value_of("to-account").expect("Please provide an account id").to_string();
+                let amount = sub_matches.value_of("amount").expect("Please provide an amount").parse::<u128>().expect("Invalid amount");
+                let release_height = sub_matches.value_of("release-height").expect("Please provide a release height").parse::<u64>().expect("Invalid release height");
+                let asset = sub_matches.value_of("asset").unwrap().to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&from_account));
+                server::escrow(from_account, to_account, amount, asset, release_height, fee, nonce, &secret_key);
+            }
+            ("cancel-escrow", Some(sub_matches)) => {
+                let from_account = sub_matches.value_of("from-account").expect("Please provide an account id").to_string();
+                let escrow = sub_matches.value_of("escrow").expect("Please provide an escrow id").to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&from_account));
+                server::cancel_escrow(from_account, escrow, fee, nonce, &secret_key);
+            }
+            ("escrows", Some(sub_matches)) => {
+                server::escrows(sub_matches.value_of("account").map(str::to_string));
+            }
+            ("schedule", Some(sub_matches)) => {
+                let from_account = sub_matches.value_of("from-account").expect("Please provide an account id").to_string();
+                let to_account = sub_matches.value_of("to-account").expect("Please provide an account id").to_string();
+                let amount = sub_matches.value_of("amount").expect("Please provide an amount").parse::<u128>().expect("Invalid amount");
+                let interval = sub_matches.value_of("every").expect("Please provide an interval").parse::<u64>().expect("Invalid interval");
+                let count = sub_matches.value_of("times").expect("Please provide a number of payments").parse::<u64>().expect("Invalid number of payments");
+                let on_failure = FailurePolicy::parse(sub_matches.value_of("on-failure").unwrap()).expect("Invalid failure policy");
+                let asset = sub_matches.value_of("asset").unwrap().to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&from_account));
+                server::schedule(from_account, to_account, amount, asset, interval, count, on_failure, fee, nonce, &secret_key);
+            }
+            ("cancel-schedule", Some(sub_matches)) => {
+                let from_account = sub_matches.value_of("from-account").expect("Please provide an account id").to_string();
+                let schedule = sub_matches.value_of("schedule").expect("Please provide a schedule id").to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&from_account));
+                server::cancel_schedule(from_account, schedule, fee, nonce, &secret_key);
+            }
+            ("schedules", Some(sub_matches)) => {
+                server::schedules(sub_matches.value_of("account").map(str::to_string));
+            }
+            ("create-multisig", Some(sub_matches)) => {
+                let creator = sub_matches.value_of("creator").expect("Please provide an account id").to_string();
+                let id_of_account = sub_matches.value_of("id-of-account").expect("Please provide an account id").to_string();
+                let threshold = sub_matches.value_of("threshold").expect("Please provide a threshold").parse::<usize>().expect("Invalid threshold");
+                let members: Vec<String> = sub_matches.values_of("members").expect("Please provide the members").map(str::to_string).collect();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&creator));
+                server::create_multisig(creator, id_of_account, members, threshold, fee, nonce, &secret_key);
+            }
+            ("propose", Some(sub_matches)) => {
+                let member = sub_matches.value_of("member").expect("Please provide an account id").to_string();
+                let multisig = sub_matches.value_of("multisig-account").expect("Please provide an account id").to_string();
+                let to_account = sub_matches.value_of("to-account").expect("Please provide an account id").to_string();
+                let amount = sub_matches.value_of("amount").expect("Please provide an amount").parse::<u128>().expect("Invalid amount");
+                let asset = sub_matches.value_of("asset").unwrap().to_string();
+                let expires_in = sub_matches.value_of("expires-in").unwrap().parse::<u64>().expect("Invalid expiry");
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&member));
+                server::propose(member, multisig, to_account, amount, asset, expires_in, fee, nonce, &secret_key);
+            }
+            ("approve", Some(sub_matches)) => {
+                let member = sub_matches.value_of("member").expect("Please provide an account id").to_string();
+                let multisig = sub_matches.value_of("multisig-account").expect("Please provide an account id").to_string();
+                let proposal = sub_matches.value_of("proposal").expect("Please provide a proposal id").to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&member));
+                server::approve(member, multisig, proposal, fee, nonce, &secret_key);
+            }
+            ("proposals", Some(sub_matches)) => {
+                server::proposals(sub_matches.value_of("account").map(str::to_string));
+            }
+            ("allow", Some(sub_matches)) => {
+                let owner = sub_matches.value_of("owner").expect("Please provide an account id").to_string();
+                let spender = sub_matches.value_of("spender").expect("Please provide an account id").to_string();
+                let amount = sub_matches.value_of("amount").expect("Please provide an amount").parse::<u128>().expect("Invalid amount");
+                let asset = sub_matches.value_of("asset").unwrap().to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&owner));
+                server::allow(owner, spender, amount, asset, fee, nonce, &secret_key);
+            }
+            ("transfer-from", Some(sub_matches)) => {
+                let spender = sub_matches.value_of("spender").expect("Please provide an account id").to_string();
+                let owner = sub_matches.value_of("owner").expect("Please provide an account id").to_string();
+                let to_account = sub_matches.value_of("to-account").expect("Please provide an account id").to_string();
+                let amount = sub_matches.value_of("amount").expect("Please provide an amount").parse::<u128>().expect("Invalid amount");
+                let asset = sub_matches.value_of("asset").unwrap().to_string();
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&spender));
+                server::transfer_from(spender, owner, to_account, amount, asset, fee, nonce, &secret_key);
+            }
+            ("allowance", Some(sub_matches)) => {
+                let owner = sub_matches.value_of("owner").expect("Please provide an account id").to_string();
+                let spender = sub_matches.value_of("spender").expect("Please provide an account id").to_string();
+                let asset = sub_matches.value_of("asset").unwrap().to_string();
+                server::allowance(owner, spender, asset);
+            }
+            ("contract", Some(contract_matches)) => {
+                match contract_matches.subcommand() {
+                    ("deploy", Some(sub_matches)) => {
+                        let deployer = sub_matches.value_of("deployer").expect("Please provide an account id").to_string();
+                        let contract = sub_matches.value_of("contract").expect("Please provide an account id").to_string();
+                        let file = sub_matches.value_of("file").expect("Please provide a source file");
+                        let code = match server::read_contract_file(file) {
+                            Ok(code) => code,
+                            Err(err) => {
+                                eprintln!("Invalid contract file: {}", err);
+                                std::process::exit(1);
+                            }
+                        };
+                        let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                        let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                        let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&deployer));
+                        server::deploy_contract(deployer, contract, code, fee, nonce, &secret_key);
+                    }
+                    ("call", Some(sub_matches)) => {
+                        let caller = sub_matches.value_of("caller").expect("Please provide an account id").to_string();
+                        let contract = sub_matches.value_of("contract").expect("Please provide an account id").to_string();
+                        let args: Vec<Value> = sub_matches.values_of("args").unwrap_or_default().map(Value::parse).collect();
+                        let value = sub_matches.value_of("value").unwrap().parse::<u128>().expect("Invalid value");
+                        let gas_limit = sub_matches.value_of("gas").unwrap().parse::<u64>().expect("Invalid gas limit");
+                        let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                        let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                        let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&caller));
+                        server::call_contract(caller, contract, value, args, gas_limit, fee, nonce, &secret_key);
+                    }
+                    ("show", Some(sub_matches)) => {
+                        let contract = sub_matches.value_of("contract").expect("Please provide an account id").to_string();
+                        server::contract(contract);
+                    }
+                    _ => {
+                        println!("{}", contract_matches.usage());
+                    }
+                }
+            }
+            ("issue-asset", Some(sub_matches)) => {
+                let issuer = sub_matches.value_of("issuer").expect("Please provide an account id").to_string();
+                let asset = sub_matches.value_of("asset").expect("Please provide an asset id").to_string();
+                let supply = sub_matches.value_of("supply").expect("Please provide a supply").parse::<u128>().expect("Invalid supply");
+                let fee = sub_matches.value_of("fee").unwrap().parse::<u128>().expect("Invalid fee");
+                let nonce = sub_matches.value_of("nonce").map(|nonce| nonce.parse::<u64>().expect("Invalid nonce"));
+                let secret_key = wallet::signing_key(sub_matches.value_of("key").unwrap_or(&issuer));
+                server::issue_asset(issuer, asset, supply, fee, nonce, &secret_key);
+            }
+            ("assets", _) => {
+                server::assets();
+            }
+            ("supply", _) => {
+                server::supply();
+            }
+            ("wallet", Some(wallet_matches)) => {
+                match wallet_matches.subcommand() {
+                    ("new", Some(sub_matches)) => {
+                        wallet::wallet_new(sub_matches.value_of("name").expect("Please provide a key name").to_string());
+                    }
+                    ("list", _) => {
+                        wallet::wallet_list();
+                    }
+                    ("import", Some(sub_matches)) => {
+                        let name = sub_matches.value_of("name").expect("Please provide a key name").to_string();
+                        wallet::wallet_import(name);
+                    }
+                    ("export", Some(sub_matches)) => {
+                        wallet::wallet_export(sub_matches.value_of("name").expect("Please provide a key name").to_string());
+                    }
+                    ("show", Some(sub_matches)) => {
+                        wallet::wallet_show(sub_matches.value_of("name").expect("Please provide a key name").to_string());
+                    }
+                    _ => {
+                        println!("{}", wallet_matches.usage());
+                    }
+                }
+            }
+            ("verify", _) => {
+                server::verify();
+            }
+            ("account", Some(sub_matches)) => {
+                let id_of_account = sub_matches.value_of("account").expect("Please provide an account id").to_string();
+                server::account(id_of_account);
+            }
+            ("history", Some(sub_matches)) => {
+                let id_of_account = sub_matches.value_of("account").expect("Please provide an account id").to_string();
+                let offset = sub_matches.value_of("offset").unwrap().parse::<usize>().expect("Invalid offset");
+                let limit = sub_matches.value_of("limit").unwrap().parse::<usize>().expect("Invalid limit");
+                server::history(id_of_account, offset, limit);
+            }
+            ("block", Some(sub_matches)) => {
+                let height = sub_matches.value_of("height").expect("Please provide a block height").to_string();
+                if height != "latest" {
+                    height.parse::<u64>().expect("Invalid block height");
+                }
+                server::block(height);
+            }
+            ("blocks", Some(sub_matches)) => {
+                let from = sub_matches.value_of("from").unwrap().parse::<u64>().expect("Invalid height");
+                let limit = sub_matches.value_of("limit").unwrap().parse::<usize>().expect("Invalid limit");
+                server::blocks(from, limit);
+            }
+            ("tx-status", Some(sub_matches)) => {
+                let id = sub_matches.value_of("id").expect("Please provide a transaction id").to_string();
+                server::tx_status(id);
+            }
+            ("mempool", _) => {
+                server::mempool();
             }
             _ => {
                 println!("{}", matches.usage());
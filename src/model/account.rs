@@ -1,37 +1,111 @@
 use anyhow::Result;
+use serde::Serialize;
+use super::{Balances, Contract, Multisig, NATIVE_ASSET};
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Account {
     id: String,
-    balance: u128,
+    /// Balance held in each asset, the native coin always included
+    balances: Balances,
+    /// Hex encoded Ed25519 key that transfers out of the account must be signed with
+    public_key: String,
+    /// Nonce the next transfer sent by the account must carry
+    nonce: u64,
+    /// Members approving the withdrawals of a multisig account, which has no key of its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    multisig: Option<Multisig>,
+    /// Code run when the account is called, which alone can move the funds of the account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contract: Option<Contract>,
 }
 
 impl Account {
-    pub fn new(id: String, balance: u128) -> Account {
+    pub fn new(id: String, balance: u128, public_key: String) -> Account {
+        let mut balances = Balances::new();
+        balances.insert(NATIVE_ASSET.to_string(), balance);
         Account {
             id,
-            balance
+            balances,
+            public_key,
+            nonce: 0,
+            multisig: None,
+            contract: None
         }
     }
 
+    /// Account without a key, whose withdrawals are approved by the members of `multisig`
+    pub fn new_multisig(id: String, multisig: Multisig) -> Account {
+        let mut account = Account::new(id, 0, String::new());
+        account.multisig = Some(multisig);
+        account
+    }
+
+    /// Account without a key, whose funds are moved by the code of `contract` only
+    pub fn new_contract(id: String, contract: Contract) -> Account {
+        let mut account = Account::new(id, 0, String::new());
+        account.contract = Some(contract);
+        account
+    }
+
     pub fn get_id(&self) -> &String {
         &self.id
     }
 
-    pub fn get_balance(&self) -> &u128 {
-        &self.balance
+    /// Balance held in `asset`, 0 for assets the account never received
+    pub fn get_balance(&self, asset: &str) -> u128 {
+        self.balances.get(asset).copied().unwrap_or(0)
+    }
+
+    pub fn get_balances(&self) -> &Balances {
+        &self.balances
+    }
+
+    pub fn get_public_key(&self) -> &String {
+        &self.public_key
+    }
+
+    pub fn get_multisig(&self) -> Option<&Multisig> {
+        self.multisig.as_ref()
+    }
+
+    pub fn get_contract(&self) -> Option<&Contract> {
+        self.contract.as_ref()
     }
 
-    pub fn withdraw(&mut self, amount: u128) -> Result<()> {
-        if self.balance < amount {
-            return Err(anyhow::anyhow!("Insufficient funds"));
+    pub fn get_contract_mut(&mut self) -> Option<&mut Contract> {
+        self.contract.as_mut()
+    }
+
+    pub fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Check the nonce carried by a transfer, rejecting replayed or out of order ones
+    pub fn check_nonce(&self, nonce: u64) -> Result<()> {
+        if nonce < self.nonce {
+            return Err(anyhow::anyhow!("Nonce {} was already used by {}, expected {}", nonce, self.id, self.nonce));
+        } else if nonce > self.nonce {
+            return Err(anyhow::anyhow!("Nonce {} skips ahead for {}, expected {}", nonce, self.id, self.nonce));
         }
-        self.balance -= amount;
         Ok(())
     }
 
-    pub fn deposit(&mut self, amount: u128) {
-        self.balance += amount;
+    pub fn use_nonce(&mut self, nonce: u64) -> Result<()> {
+        self.check_nonce(nonce)?;
+        self.nonce = self.nonce.checked_add(1).ok_or_else(|| anyhow::anyhow!("Nonces of {} are exhausted", self.id))?;
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, asset: &str, amount: u128) -> Result<()> {
+        let balance = self.get_balance(asset).checked_sub(amount).ok_or_else(|| anyhow::anyhow!("Insufficient funds"))?;
+        self.balances.insert(asset.to_string(), balance);
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, asset: &str, amount: u128) -> Result<()> {
+        let balance = self.get_balance(asset).checked_add(amount).ok_or_else(|| anyhow::anyhow!("Balance of {} in {} would overflow", self.id, asset))?;
+        self.balances.insert(asset.to_string(), balance);
+        Ok(())
     }
 }
@@ -0,0 +1,171 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::{Signed, SigningPayload, NATIVE_ASSET};
+
+/// Transaction by which an owner lets a spender move up to `amount` of its funds, replacing any previous allowance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowanceApproval {
+    owner: String,
+    spender: String,
+    amount: u128,
+    asset: String,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Transaction by which a spender moves funds of an owner, using up its allowance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowanceTransfer {
+    spender: String,
+    owner: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+impl AllowanceApproval {
+    pub fn new(owner: String, spender: String, amount: u128, asset: String, fee: u128, nonce: u64) -> AllowanceApproval {
+        AllowanceApproval {
+            owner,
+            spender,
+            amount,
+            asset,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_owner_id(&self) -> &String {
+        &self.owner
+    }
+
+    pub fn get_spender_id(&self) -> &String {
+        &self.spender
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for AllowanceApproval {
+    fn get_signer(&self) -> &String {
+        &self.owner
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("allowance_approval")
+            .string(&self.owner)
+            .string(&self.spender)
+            .u128(self.amount)
+            .string(&self.asset)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for AllowanceApproval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Allowance: {} allows {} to spend {}{} (nonce {}, fee {}{})", self.owner, self.spender, self.amount, self.asset, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl AllowanceTransfer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(spender: String, owner: String, receiver: String, amount: u128, asset: String, fee: u128, nonce: u64) -> AllowanceTransfer {
+        AllowanceTransfer {
+            spender,
+            owner,
+            receiver,
+            amount,
+            asset,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_spender_id(&self) -> &String {
+        &self.spender
+    }
+
+    pub fn get_owner_id(&self) -> &String {
+        &self.owner
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for AllowanceTransfer {
+    fn get_signer(&self) -> &String {
+        &self.spender
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("allowance_transfer")
+            .string(&self.spender)
+            .string(&self.owner)
+            .string(&self.receiver)
+            .u128(self.amount)
+            .string(&self.asset)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for AllowanceTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Transfer from: {} moves {} -> {} : {}{} (nonce {}, fee {}{})", self.spender, self.owner, self.receiver, self.amount, self.asset, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::{Signed, SigningPayload};
+
+/// Asset of the chain itself, held by every account from its creation
+pub const NATIVE_ASSET: &str = "b";
+
+/// Token issued on the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Asset {
+    id: String,
+    issuer: String,
+    supply: u128,
+}
+
+/// Transaction creating an asset, its whole supply credited to the issuer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetIssue {
+    issuer: String,
+    asset: String,
+    supply: u128,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+impl Asset {
+    pub fn new(id: String, issuer: String, supply: u128) -> Asset {
+        Asset {
+            id,
+            issuer,
+            supply
+        }
+    }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+}
+
+impl AssetIssue {
+    pub fn new(issuer: String, asset: String, supply: u128, fee: u128, nonce: u64) -> AssetIssue {
+        AssetIssue {
+            issuer,
+            asset,
+            supply,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_issuer(&self) -> &String {
+        &self.issuer
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_supply(&self) -> u128 {
+        self.supply
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for AssetIssue {
+    fn get_signer(&self) -> &String {
+        &self.issuer
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("issue_asset")
+            .string(&self.issuer)
+            .string(&self.asset)
+            .u128(self.supply)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for AssetIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Issue asset: {} : {}{} (nonce {}, fee {}{})", self.issuer, self.supply, self.asset, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::{Signed, SigningPayload, NATIVE_ASSET};
+
+/// One payment of a batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchLeg {
+    receiver: String,
+    amount: u128,
+    asset: String,
+}
+
+/// Payments from one sender that are all executed, or none of them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchTransfer {
+    sender: String,
+    legs: Vec<BatchLeg>,
+    /// Paid once for the whole batch, in the native coin
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+impl BatchLeg {
+    pub fn new(receiver: String, amount: u128, asset: String) -> BatchLeg {
+        BatchLeg {
+            receiver,
+            amount,
+            asset
+        }
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+}
+
+impl BatchTransfer {
+    pub fn new(sender: String, legs: Vec<BatchLeg>, fee: u128, nonce: u64) -> BatchTransfer {
+        BatchTransfer {
+            sender,
+            legs,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_legs(&self) -> &Vec<BatchLeg> {
+        &self.legs
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for BatchTransfer {
+    fn get_signer(&self) -> &String {
+        &self.sender
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        let mut payload = SigningPayload::new("batch")
+            .string(&self.sender)
+            .u64(self.legs.len() as u64);
+        for leg in &self.legs {
+            payload = payload
+                .string(&leg.receiver)
+                .u128(leg.amount)
+                .string(&leg.asset);
+        }
+        payload
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for BatchTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Batch: {} -> {} legs (nonce {}, fee {}{})", self.sender, self.legs.len(), self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
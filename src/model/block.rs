@@ -1,27 +1,103 @@
 use std::fmt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use super::{Signed, SigningPayload, Transaction, NATIVE_ASSET};
 
-#[derive(Debug, Clone)]
-pub struct Transfer { 
+/// Parent hash recorded in the header of the first block of the chain
+pub const GENESIS_PARENT_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transfer {
     sender: String,
     receiver: String,
-    amount: u128
+    amount: u128,
+    #[serde(default = "native_asset")]
+    asset: String,
+    /// Paid in the native coin to the producer of the block including the transfer
+    #[serde(default)]
+    fee: u128,
+    nonce: u64,
+    signature: String
+}
+
+fn native_asset() -> String {
+    NATIVE_ASSET.to_string()
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    height: u64,
+    timestamp: u64,
+    parent_hash: String,
+    transactions_root: String,
+    /// Account credited with the fees of the block, if any
+    #[serde(default)]
+    producer: Option<String>,
+    /// Total fees paid by the transactions of the block
+    #[serde(default)]
+    fees: u128,
+    /// Gas budget of the block when it was minted
+    #[serde(default)]
+    gas_limit: u64,
+    /// Gas of the transactions of the block, scheduled payments included
+    #[serde(default)]
+    gas_used: u64,
+    hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Block {
-    transfers: Vec<Transfer>
+    header: BlockHeader,
+    transactions: Vec<Transaction>
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Merkle root of the given leaf hashes, carrying the last node of odd levels up
+/// unchanged so that `[a, b, c]` and `[a, b, c, c]` get different roots
+fn merkle_root(mut level: Vec<String>) -> String {
+    if level.is_empty() {
+        return sha256_hex(&[]);
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => sha256_hex(format!("{}{}", left, right).as_bytes()),
+                [odd] => odd.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level.pop().unwrap()
 }
 
 
 impl Transfer {
-    pub fn new(sender: String, receiver: String, amount: u128) -> Transfer {
+    pub fn new(sender: String, receiver: String, amount: u128, nonce: u64) -> Transfer {
         Transfer {
             sender,
             receiver,
-            amount
+            amount,
+            asset: native_asset(),
+            fee: 0,
+            nonce,
+            signature: String::new()
         }
     }
 
+    pub fn with_asset(mut self, asset: String) -> Transfer {
+        self.asset = asset;
+        self
+    }
+
+    pub fn with_fee(mut self, fee: u128) -> Transfer {
+        self.fee = fee;
+        self
+    }
+
     pub fn get_sender_id(&self) -> &String {
         &self.sender
     }
@@ -33,26 +109,127 @@ impl Transfer {
     pub fn get_amount(&self) -> &u128 {
         &self.amount
     }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for Transfer {
+    fn get_signer(&self) -> &String {
+        &self.sender
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("transfer")
+            .string(&self.sender)
+            .string(&self.receiver)
+            .u128(self.amount)
+            .string(&self.asset)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
 }
 
 impl fmt::Display for Transfer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Transaction: {} -> {} : {}b", self.sender, self.receiver, self.amount)
+        write!(f, "Transaction: {} -> {} : {}{} (nonce {}, fee {}{})", self.sender, self.receiver, self.amount, self.asset, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl BlockHeader {
+    pub fn get_height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_parent_hash(&self) -> &String {
+        &self.parent_hash
+    }
+
+    pub fn get_transactions_root(&self) -> &String {
+        &self.transactions_root
+    }
+
+    pub fn get_producer(&self) -> Option<&String> {
+        self.producer.as_ref()
+    }
+
+    pub fn get_gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    pub fn get_hash(&self) -> &String {
+        &self.hash
+    }
+
+    /// Hash of every header field except the hash itself
+    pub fn compute_hash(&self) -> String {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.height.to_be_bytes());
+        data.extend_from_slice(&self.timestamp.to_be_bytes());
+        data.extend_from_slice(self.parent_hash.as_bytes());
+        data.extend_from_slice(self.transactions_root.as_bytes());
+        data.extend_from_slice(self.producer.as_deref().unwrap_or_default().as_bytes());
+        data.extend_from_slice(&self.fees.to_be_bytes());
+        data.extend_from_slice(&self.gas_limit.to_be_bytes());
+        data.extend_from_slice(&self.gas_used.to_be_bytes());
+        sha256_hex(&data)
     }
 }
 
 impl Block {
-    pub fn new() -> Block {
-        Block {
-            transfers: Vec::new()
-        }
+    /// Seal the transactions into a block, computing its header and the gas they use
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(height: u64, timestamp: u64, parent_hash: String, producer: Option<String>, fees: u128, gas_limit: u64, transactions: Vec<Transaction>) -> Block {
+        let gas_used = transactions.iter().map(Transaction::gas).fold(0, u64::saturating_add);
+        let mut block = Block {
+            header: BlockHeader {
+                height,
+                timestamp,
+                parent_hash,
+                transactions_root: String::new(),
+                producer,
+                fees,
+                gas_limit,
+                gas_used,
+                hash: String::new(),
+            },
+            transactions
+        };
+        block.header.transactions_root = block.compute_transactions_root();
+        block.header.hash = block.header.compute_hash();
+        block
+    }
+
+    pub fn get_header(&self) -> &BlockHeader {
+        &self.header
     }
 
-    pub fn add_transfer(&mut self, transaction: Transfer) {
-        self.transfers.push(transaction);
+    pub fn get_transactions(&self) -> &Vec<Transaction> {
+        &self.transactions
     }
 
-    pub fn clear(&mut self) {
-        self.transfers.clear();
+    pub fn compute_transactions_root(&self) -> String {
+        merkle_root(self.transactions.iter().map(Transaction::hash).collect())
     }
 }
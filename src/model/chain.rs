@@ -1,131 +1,965 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::time::Duration;
-use std::{collections::HashMap, time::Instant};
-use super::{Block, Account, Transfer};
+use std::collections::{BTreeMap, HashMap};
+use super::{apply, authorize, check_signature, Account, AccountHistory, AllowanceApproval, AllowanceTransfer, Asset, AssetIssue, BalanceHistory, Balances, BatchTransfer, Clock, Contract, ContractCall, ContractDeploy, Escrow, EscrowCancel, EscrowTransfer, FailurePolicy, MultisigApproval, MultisigCreate, MultisigProposal, Proposal, Receipt, Schedule, ScheduleCancel, ScheduleCreate, ScheduledPayment, Signed, Simulation, Block, BlockStore, Config, Mempool, State, Transaction, TransactionReceipt, TransactionStatus, Transfer, GENESIS_PARENT_HASH, MAX_BLOCK_TRANSACTIONS, MAX_CALL_GAS, MAX_TRANSACTION_SIZE, NATIVE_ASSET};
 use anyhow::Result;
-use std::boxed::Box;
 
-pub struct Transaction {
-    pub transaction: Box<dyn FnOnce() -> Result<()> + Send>,
-    pub callback: Box<dyn FnOnce(Result<()>) -> () + Send>,
+/// Blocks a simulation mints ahead of the chain before giving up on including its transaction
+const MAX_SIMULATED_BLOCKS: u64 = 10_000;
+
+/// Rejection of a transaction the node cannot take right now, although it may be valid once it can
+#[derive(Debug)]
+pub struct Unavailable(pub String);
+
+impl std::fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
+impl std::error::Error for Unavailable {}
 
 pub struct Blockchain {
     blockchain: Vec<Block>,
-    accounts: Arc<Mutex<HashMap<String, Account>>>,
-    current_block: Arc<Mutex<Block>>,
-    transactions: Vec<Transaction>,
-    last_process_time: Instant,
-    process_interval: Duration
+    state: State,
+    mempool: Mempool,
+    /// Receipts of each block, in transaction order
+    receipts: Vec<Vec<TransactionReceipt>>,
+    /// Position of the receipt of each transaction ID, its first successful one once it succeeded
+    receipt_positions: HashMap<String, (u64, usize)>,
+    /// Why transactions taken from the mempool were left out of their block, by ID
+    dropped: HashMap<String, String>,
+    balance_history: BalanceHistory,
+    account_history: AccountHistory,
+    store: Option<BlockStore>,
+    config: Config,
+    clock: Arc<dyn Clock>,
+    /// Start of the slot grid blocks are minted on, in milliseconds since the Unix epoch
+    genesis_time: u64,
+    /// Slot the next block is due at, in milliseconds since the Unix epoch
+    next_block_time: u64,
+    /// Why minting stopped, once a block broke the supply invariant or could not be persisted
+    halted: Option<String>
 }
 
 impl Blockchain {
-    pub fn new() -> Blockchain {
+    /// Create the chain, replaying the block log first when the config has a data directory
+    pub fn new(config: Config, clock: Arc<dyn Clock>) -> Result<Blockchain> {
+        if config.block_transactions > MAX_BLOCK_TRANSACTIONS {
+            return Err(anyhow::anyhow!("A block may hold at most {} transactions, {} requested", MAX_BLOCK_TRANSACTIONS, config.block_transactions));
+        }
         let blockchain = Vec::new();
-        let accounts = Arc::new(Mutex::new(HashMap::new()));
-        let current_block = Arc::new(Mutex::new(Block::new()));
-        let last_process_time = Instant::now();
-        let process_interval = Duration::from_secs(10);
+        let state = State::new();
+        let mempool = Mempool::new(config.mempool_size);
+        let (store, stored_blocks) = match &config.data_dir {
+            Some(data_dir) => {
+                let (store, blocks) = BlockStore::open(data_dir)?;
+                (Some(store), blocks)
+            }
+            None => (None, Vec::new()),
+        };
 
-        Blockchain {
+        let mut chain = Blockchain {
             blockchain,
-            accounts,
-            transactions: Vec::new(),
-            current_block,
-            last_process_time,
-            process_interval
+            state,
+            mempool,
+            receipts: Vec::new(),
+            receipt_positions: HashMap::new(),
+            dropped: HashMap::new(),
+            balance_history: BalanceHistory::new(),
+            account_history: AccountHistory::new(),
+            store,
+            config,
+            clock,
+            genesis_time: 0,
+            next_block_time: 0,
+            halted: None
+        };
+        chain.replay(stored_blocks)?;
+        // Slots count from the first block, or from now for a new chain
+        let now = chain.clock.now();
+        chain.genesis_time = chain.blockchain.first().map_or(now, |block| block.get_header().get_timestamp());
+        chain.next_block_time = chain.next_slot(now);
+        Ok(chain)
+    }
+
+    pub fn get_balance(&self, id: &String, asset: &str) -> Result<u128> {
+        self.check_asset(asset)?;
+        match self.state.get_account(id) {
+            Some(account) => Ok(account.get_balance(asset)),
+            None => Err(anyhow::anyhow!("Account not found")),
+        }
+    }
+
+    fn check_asset(&self, asset: &str) -> Result<()> {
+        if asset != NATIVE_ASSET && !self.state.contains_asset(&asset.to_string()) {
+            return Err(anyhow::anyhow!("Asset {} not found", asset));
         }
+        Ok(())
     }
 
-    pub fn get_balance(&self, id: &String) -> Result<u128> {
-        let accounts = self.accounts.lock().unwrap();
-        let account = accounts.get(id);
-        match account {
-            Some(account) => Ok(account.get_balance().clone()),
+    /// Issued assets, sorted by ID
+    pub fn get_assets(&self) -> Vec<&Asset> {
+        self.state.get_assets().collect()
+    }
+
+    pub fn get_account(&self, id: &String) -> Result<&Account> {
+        match self.state.get_account(id) {
+            Some(account) => Ok(account),
             None => Err(anyhow::anyhow!("Account not found")),
         }
     }
 
-    pub fn create_account(
-        &mut self,
-        id: String,
-        balance: u128,
-        callback: Box<dyn FnOnce(Result<()>) -> () + Send + 'static>,
-    ) {
-        let accounts_ref = Arc::clone(&self.accounts);
+    /// Nonce for the next signed transaction of an account, counting the ones it has waiting in the mempool
+    pub fn get_next_nonce(&self, id: &String) -> Result<u64> {
+        let confirmed = self.get_account(id)?.get_nonce();
+        Ok(confirmed.max(self.get_pending_nonce(id)))
+    }
+
+    /// Nonce following the ones an account has waiting in the mempool, 0 when it has none
+    fn get_pending_nonce(&self, id: &String) -> u64 {
+        self.mempool
+            .iter()
+            .filter_map(|pending| match pending.transaction.signed() {
+                Some(signed) if signed.get_signer() == id => Some(signed.get_nonce() + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Balance of an account in `asset` at the end of block `height`
+    pub fn get_balance_at(&self, id: &String, asset: &str, height: u64) -> Result<u128> {
+        if height >= self.get_height() {
+            return Err(anyhow::anyhow!("Block {} has not been minted yet", height));
+        }
+        self.check_asset(asset)?;
+        match self.balance_history.balance_at(id, asset, height) {
+            Some(balance) => Ok(balance),
+            None => Err(anyhow::anyhow!("Account not found at block {}", height)),
+        }
+    }
+
+    /// Total number of transactions involving the account and a page of them with their receipts, oldest first
+    pub fn get_history(&self, id: &String, offset: usize, limit: usize) -> Result<(usize, Vec<(&Transaction, &TransactionReceipt)>)> {
+        if !self.account_history.contains(id) && !self.state.contains_account(id) {
+            return Err(anyhow::anyhow!("Account not found"));
+        }
+        let (total, entries) = self.account_history.page(id, offset, limit);
+        let page = entries
+            .iter()
+            .map(|(height, index)| (&self.blockchain[*height as usize].get_transactions()[*index], &self.receipts[*height as usize][*index]))
+            .collect();
+        Ok((total, page))
+    }
+
+    pub fn get_block(&self, height: u64) -> Option<&Block> {
+        self.blockchain.get(height as usize)
+    }
+
+    pub fn get_latest_block(&self) -> Option<&Block> {
+        self.blockchain.last()
+    }
+
+    /// Up to `limit` blocks starting at height `from`
+    pub fn get_blocks(&self, from: u64, limit: usize) -> &[Block] {
+        let start = (from as usize).min(self.blockchain.len());
+        let end = start.saturating_add(limit).min(self.blockchain.len());
+        &self.blockchain[start..end]
+    }
+
+    pub fn get_height(&self) -> u64 {
+        self.blockchain.len() as u64
+    }
+
+    /// Receipt of a confirmed transaction; a transaction executed again keeps its first successful receipt
+    pub fn get_receipt(&self, id: &String) -> Option<&TransactionReceipt> {
+        let (height, index) = self.receipt_positions.get(id)?;
+        Some(&self.receipts[*height as usize][*index])
+    }
+
+    /// Why a transaction was left out of the block it was taken for, if it was
+    pub fn get_dropped(&self, id: &String) -> Option<&String> {
+        self.dropped.get(id)
+    }
+
+    /// Amount of each asset in existence
+    pub fn get_supply(&self) -> &Balances {
+        self.state.get_supply()
+    }
+
+    /// Escrows still holding funds, those sent or received by `account` only when given
+    pub fn get_escrows(&self, account: Option<&String>) -> Vec<&Escrow> {
+        self.state
+            .get_escrows()
+            .filter(|escrow| account.is_none_or(|id| escrow.get_sender_id() == id || escrow.get_receiver_id() == id))
+            .collect()
+    }
+
+    /// Schedules with payments left, those sent or received by `account` only when given
+    pub fn get_schedules(&self, account: Option<&String>) -> Vec<&Schedule> {
+        self.state
+            .get_schedules()
+            .filter(|schedule| account.is_none_or(|id| schedule.get_sender_id() == id || schedule.get_receiver_id() == id))
+            .collect()
+    }
+
+    /// Proposals waiting for approvals, those of the multisig `account` only when given
+    pub fn get_proposals(&self, account: Option<&String>) -> Vec<&Proposal> {
+        self.state
+            .get_proposals()
+            .filter(|proposal| account.is_none_or(|id| proposal.get_account_id() == id))
+            .collect()
+    }
+
+    /// Amount of `asset` the spender may still move out of the owner's account
+    pub fn get_allowance(&self, owner: &String, spender: &String, asset: &str) -> Result<u128> {
+        self.get_account(owner)?;
+        self.get_account(spender)?;
+        self.check_asset(asset)?;
+        Ok(self.state.get_allowance(owner, spender, asset))
+    }
+
+    /// Code and storage of a contract account
+    pub fn get_contract(&self, id: &String) -> Result<&Contract> {
+        self.get_account(id)?
+            .get_contract()
+            .ok_or_else(|| anyhow::anyhow!("Account {} is not a contract", id))
+    }
+
+    pub fn get_halted(&self) -> Option<&String> {
+        self.halted.as_ref()
+    }
 
-        let closure = move || -> Result<()> {
-            let mut accounts = accounts_ref.lock().unwrap();
-            if accounts.contains_key(&id) {
-                return Err(anyhow::anyhow!("Account already exists"));
+    pub fn get_mempool(&self) -> &Mempool {
+        &self.mempool
+    }
+
+    /// Run a transaction against a copy of the chain, as if it were queued now and the coming blocks minted, without queuing it.
+    /// The copied blocks select, drop and settle transactions exactly like minting does, so the receipt gives the block and position
+    /// the transaction would get; admission checks fail like submissions do.
+    pub fn simulate(&self, transaction: &Transaction) -> Result<Simulation> {
+        self.admit(transaction)?;
+        let id = transaction.hash();
+        let mut state = self.state.clone();
+        let mut mempool = self.mempool.clone();
+        mempool.push(transaction.clone()).map_err(|err| Unavailable(err.to_string()))?;
+        let producer = self.config.producer.as_ref();
+        for height in self.get_height()..self.get_height() + MAX_SIMULATED_BLOCKS {
+            let mut run = BlockRun { state: &mut state, height, verbose: false };
+            let included = run.include(&mut mempool, &self.config);
+            run.finish(producer, &included.transactions);
+            if let Some((_, reason)) = included.dropped.iter().find(|(dropped, _)| dropped.hash() == id) {
+                return Err(anyhow::anyhow!("Transaction would be dropped: {}", reason));
             }
-            let account = Account::new(id.clone(), balance);
-            println!("  New account created: {}", account.get_id());
-            accounts.insert(account.get_id().clone(), account);
-            Ok(())
-        };
+            if let Some(index) = included.transactions.iter().position(|included| included.hash() == id) {
+                let receipt = TransactionReceipt::new(id, &included.results[index], height, index);
+                let balances: BTreeMap<String, Balances> = transaction
+                    .accounts()
+                    .into_iter()
+                    .filter_map(|id| state.get_account(id).map(|account| (id.clone(), account.get_balances().clone())))
+                    .collect();
+                return Ok(Simulation { receipt, balances });
+            }
+        }
+        Err(anyhow::anyhow!("Transaction would not be included within {} blocks", MAX_SIMULATED_BLOCKS))
+    }
+
+    /// Checks a transaction must pass to be queued: minting has not halted, it is small enough, stays within the call gas limit
+    /// and the block gas budget, offers at least the minimum fee and, when signed, is authorized by its signer
+    fn admit(&self, transaction: &Transaction) -> Result<()> {
+        if let Some(reason) = &self.halted {
+            return Err(Unavailable(format!("Minting halted ({}), no transaction is accepted", reason)).into());
+        }
+        let size = serde_json::to_vec(transaction)?.len();
+        if size > MAX_TRANSACTION_SIZE {
+            return Err(anyhow::anyhow!("Transaction of {} bytes exceeds the limit of {} bytes", size, MAX_TRANSACTION_SIZE));
+        }
+        if let Transaction::Call(call) = transaction {
+            if call.get_gas_limit() > MAX_CALL_GAS {
+                return Err(anyhow::anyhow!("Gas limit {} is above the maximum of {}", call.get_gas_limit(), MAX_CALL_GAS));
+            }
+        }
+        if transaction.gas() > self.config.block_gas {
+            return Err(anyhow::anyhow!("Transaction needs {} gas, above the block gas budget of {}", transaction.gas(), self.config.block_gas));
+        }
+        if let Some(fee) = transaction.fee() {
+            if fee < self.config.min_fee {
+                return Err(anyhow::anyhow!("Fee {} is below the minimum fee of {}", fee, self.config.min_fee));
+            }
+        }
+        match transaction.signed() {
+            Some(signed) => self.check_signer(signed),
+            None => Ok(()),
+        }
+    }
 
-        self.transactions.push(Transaction {
-            transaction: Box::new(closure),
-            callback,
-        });
-    }
-        
-    pub fn add_transfer(
-        &mut self,
-        sender: String,
-        receiver: String,
-        amount: u128,
-        callback: Box<dyn FnOnce(Result<()>) -> () + Send>,
-    ) {
-        let transfer = Transfer::new(sender.clone(), receiver.clone(), amount);
-        let accounts_ref = Arc::clone(&self.accounts);
-        let current_block_ref = Arc::clone(&self.current_block);
-        
-        let closure = move || -> Result<()> {
-            let mut accounts = accounts_ref.lock().unwrap();
-            if !accounts.contains_key(&sender) {
-                return Err(anyhow::anyhow!("Account {} not found", transfer.get_sender_id()));
-            } else if !accounts.contains_key(&receiver) {
-                return Err(anyhow::anyhow!("Account {} not found", transfer.get_receiver_id()));
-            } else if transfer.get_amount() <= &0 {
-                return Err(anyhow::anyhow!("Transfer amount must be greater than 0"));
-            }
-    
-            let able_to_withdraw = accounts.get_mut(&sender).unwrap().withdraw(*transfer.get_amount());
-            if able_to_withdraw.is_err() {
-                println!("  Transfer failed - Sender does not have sufficient funds: {} -> {} : {}b", transfer.get_sender_id(), transfer.get_receiver_id(), transfer.get_amount());
-                return Err(anyhow::anyhow!("Insufficient funds"));
-            }
-            accounts.get_mut(&receiver).unwrap().deposit(*transfer.get_amount());
-            current_block_ref.lock().unwrap().add_transfer(transfer.clone());
-            println!("  Transfer complete: {} -> {} : {}b", transfer.get_sender_id(), transfer.get_receiver_id(), transfer.get_amount());
-            Ok(())
+    /// Check that the signer of a transaction exists or is created by a pending transaction, that the signature matches
+    /// its key and that the nonce follows the confirmed and pending transactions of the signer
+    fn check_signer(&self, signed: &dyn Signed) -> Result<()> {
+        let id = signed.get_signer();
+        let created;
+        let signer = match self.state.get_account(id) {
+            Some(account) => account,
+            None => {
+                created = self.mempool
+                    .iter()
+                    .find_map(|pending| match &pending.transaction {
+                        Transaction::CreateAccount { id: created, balance, public_key } if created == id => Some(Account::new(id.clone(), *balance, public_key.clone())),
+                        _ => None,
+                    })
+                    .ok_or_else(|| anyhow::anyhow!("Account {} not found", id))?;
+                &created
+            }
         };
-    
-        self.transactions.push(Transaction {
-            transaction: Box::new(closure),
-            callback,
-        });
+        check_signature(signer, signed)?;
+        let expected = signer.get_nonce().max(self.get_pending_nonce(id));
+        if signed.get_nonce() < expected {
+            return Err(anyhow::anyhow!("Nonce {} was already used by {}, expected {}", signed.get_nonce(), id, expected));
+        } else if signed.get_nonce() > expected {
+            return Err(anyhow::anyhow!("Nonce {} skips ahead for {}, expected {}", signed.get_nonce(), id, expected));
+        }
+        Ok(())
+    }
+
+    /// Queue a transaction for the next block and return its ID, unless it fails admission or is already pending or confirmed
+    pub fn submit(&mut self, transaction: Transaction) -> Result<String> {
+        // Duplicates are reported as such rather than by the nonce they reuse
+        let id = transaction.hash();
+        if let Some(receipt) = self.get_receipt(&id) {
+            if receipt.status == TransactionStatus::Success {
+                return Err(anyhow::anyhow!("Transaction {} is already confirmed in block {}", id, receipt.height));
+            }
+        }
+        if self.mempool.position(&id).is_some() {
+            return Err(anyhow::anyhow!("Transaction {} is already pending", id));
+        }
+        self.admit(&transaction)?;
+        self.mempool.push(transaction).map_err(|err| Unavailable(err.to_string()))?;
+        Ok(id)
+    }
+
+    pub fn create_account(&mut self, id: String, balance: u128, public_key: String) -> Result<String> {
+        self.submit(Transaction::CreateAccount { id, balance, public_key })
+    }
+
+    /// Queue a transfer signed by its sender
+    pub fn add_transfer(&mut self, transfer: Transfer) -> Result<String> {
+        self.submit(Transaction::Transfer(transfer))
+    }
+
+    /// Queue a batch of transfers signed by its sender
+    pub fn add_batch(&mut self, batch: BatchTransfer) -> Result<String> {
+        self.submit(Transaction::Batch(batch))
+    }
+
+    /// Queue a transfer into escrow
+    pub fn add_escrow(&mut self, escrow: EscrowTransfer) -> Result<String> {
+        self.submit(Transaction::Escrow(escrow))
+    }
 
+    pub fn cancel_escrow(&mut self, cancel: EscrowCancel) -> Result<String> {
+        self.submit(Transaction::CancelEscrow(cancel))
+    }
+
+    /// Queue the creation of a schedule
+    pub fn add_schedule(&mut self, schedule: ScheduleCreate) -> Result<String> {
+        self.submit(Transaction::Schedule(schedule))
+    }
+
+    pub fn cancel_schedule(&mut self, cancel: ScheduleCancel) -> Result<String> {
+        self.submit(Transaction::CancelSchedule(cancel))
+    }
+
+    /// Queue the creation of a multisig account signed by its creator
+    pub fn create_multisig(&mut self, create: MultisigCreate) -> Result<String> {
+        self.submit(Transaction::CreateMultisig(create))
+    }
+
+    /// Queue a withdrawal proposal of a multisig member
+    pub fn propose(&mut self, proposal: MultisigProposal) -> Result<String> {
+        self.submit(Transaction::Propose(proposal))
+    }
+
+    pub fn approve(&mut self, approval: MultisigApproval) -> Result<String> {
+        self.submit(Transaction::Approve(approval))
+    }
+
+    /// Queue an allowance set by its owner
+    pub fn approve_allowance(&mut self, approval: AllowanceApproval) -> Result<String> {
+        self.submit(Transaction::ApproveAllowance(approval))
+    }
+
+    /// Queue a transfer by a spender out of an owner's account
+    pub fn transfer_from(&mut self, transfer: AllowanceTransfer) -> Result<String> {
+        self.submit(Transaction::TransferFrom(transfer))
+    }
+
+    /// Queue the deploy of a contract
+    pub fn deploy_contract(&mut self, deploy: ContractDeploy) -> Result<String> {
+        self.submit(Transaction::Deploy(deploy))
+    }
+
+    /// Queue a contract call
+    pub fn call_contract(&mut self, call: ContractCall) -> Result<String> {
+        self.submit(Transaction::Call(call))
+    }
+
+    /// Queue the issue of an asset signed by its issuer
+    pub fn issue_asset(&mut self, issue: AssetIssue) -> Result<String> {
+        self.submit(Transaction::IssueAsset(issue))
     }
 
     pub fn mint(&mut self) {
-        if self.last_process_time.elapsed() > self.process_interval {
+        let now = self.clock.now();
+        if now >= self.next_block_time {
+            let slot = self.next_block_time;
+            // Missed slots are skipped rather than minted in a burst
+            self.next_block_time = self.next_slot(now);
+            let height = self.get_height();
+            let empty = self.mempool.is_empty() && self.state.due_payments(height).is_empty();
+            if self.halted.is_some() || (self.config.skip_empty && empty) {
+                return;
+            }
             println!("Minting block...");
-            self.last_process_time = Instant::now();
-            // Execute the transactions
-            while let Some(transaction) = self.transactions.pop() {
-                let result = (transaction.transaction)();        
-                (transaction.callback)(result);
-            }
-            self.transactions.clear();
-            // Add the current block to the blockchain and create a new block
-            let mut current_block = self.current_block.lock().unwrap();
-            self.blockchain.push(current_block.clone());
-            current_block.clear();
+            let producer = self.config.producer.clone();
+            let mut run = BlockRun { state: &mut self.state, height, verbose: true };
+            let included = run.include(&mut self.mempool, &self.config);
+            let fees = run.finish(producer.as_ref(), &included.transactions);
+            for (transaction, reason) in included.dropped {
+                self.dropped.insert(transaction.hash(), reason);
+            }
+            for (index, (transaction, result)) in included.transactions.iter().zip(&included.results).enumerate() {
+                self.record(height, index, transaction, result);
+            }
+            self.record_balances(height);
+            // Seal the current block on top of the last one, stamped with its slot
+            let block = Block::new(height, slot, self.get_last_hash(), producer, fees, self.config.block_gas, included.transactions);
+            if let Some(store) = &mut self.store {
+                // The state already includes the block, so it is kept in memory; minting stops as any later block
+                // would be lost on the next start along with it
+                if let Err(err) = store.append(&block) {
+                    println!("Block {} could not be persisted: {}. Minting halted", height, err);
+                    self.halted = Some(format!("Block {} could not be persisted: {}", height, err));
+                }
+            }
+            println!("  Block {} sealed: {}", block.get_header().get_height(), block.get_header().get_hash());
+            self.blockchain.push(block);
+            self.check_invariants(height);
         }        
     }
 
+    /// Time left until the next block is due
+    pub fn time_to_next_block(&self) -> Duration {
+        Duration::from_millis(self.next_block_time.saturating_sub(self.clock.now()))
+    }
+
+    /// First slot boundary after `now`, slots being `block_time` apart from the genesis time
+    fn next_slot(&self, now: u64) -> u64 {
+        let interval = (self.config.block_time.as_millis() as u64).max(1);
+        if now < self.genesis_time {
+            return self.genesis_time;
+        }
+        let elapsed_slots = (now - self.genesis_time) / interval;
+        self.genesis_time.saturating_add(elapsed_slots.saturating_add(1).saturating_mul(interval))
+    }
+
+    fn get_last_hash(&self) -> String {
+        match self.blockchain.last() {
+            Some(parent) => parent.get_header().get_hash().clone(),
+            None => GENESIS_PARENT_HASH.to_string(),
+        }
+    }
+
+    /// Record the receipt of the transaction executed at `index` in the block at `height`
+    fn record(&mut self, height: u64, index: usize, transaction: &Transaction, result: &Result<Receipt>) {
+        let receipt = TransactionReceipt::new(transaction.hash(), result, height, index);
+        for id in transaction.accounts() {
+            self.account_history.record(id, height, index);
+        }
+        // Blocks without transactions have no receipts
+        while self.receipts.len() <= height as usize {
+            self.receipts.push(Vec::new());
+        }
+        self.receipts[height as usize].push(receipt.clone());
+        // A transaction executed again keeps pointing at its first successful receipt
+        let confirmed = matches!(self.get_receipt(&receipt.id), Some(first) if first.status == TransactionStatus::Success);
+        if !confirmed {
+            self.receipt_positions.insert(receipt.id, (height, index));
+        }
+    }
+
+    /// Record the balances of the accounts the block at `height` touched
+    fn record_balances(&mut self, height: u64) {
+        for id in self.state.take_touched() {
+            let balances = self.state.get_account(&id).unwrap().get_balances().clone();
+            self.balance_history.record(&id, height, balances);
+        }
+    }
+
+    /// Check that the balances still add up to the supply after the block at `height`.
+    /// A broken invariant is a bug: debug builds panic, release builds stop minting so no further block builds on it.
+    fn check_invariants(&mut self, height: u64) {
+        if let Err(err) = self.state.check_supply() {
+            if cfg!(debug_assertions) {
+                panic!("Block {}: {}", height, err);
+            }
+            println!("Block {}: {}. Minting halted", height, err);
+            self.halted = Some(format!("Block {}: {}", height, err));
+        }
+    }
+
+    /// Rebuild the state by executing stored blocks again, checking that each one seals to the same header
+    fn replay(&mut self, blocks: Vec<Block>) -> Result<()> {
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        println!("Replaying {} blocks...", blocks.len());
+        for block in blocks {
+            let height = self.get_height();
+            let header = block.get_header();
+            let mut run = BlockRun { state: &mut self.state, height, verbose: true };
+            let results: Vec<Result<Receipt>> = block.get_transactions().iter().map(|transaction| run.execute(transaction)).collect();
+            let fees = run.finish(header.get_producer(), block.get_transactions());
+            for (index, (transaction, result)) in block.get_transactions().iter().zip(&results).enumerate() {
+                self.record(height, index, transaction, result);
+            }
+            self.record_balances(height);
+            let sealed = Block::new(height, header.get_timestamp(), self.get_last_hash(), header.get_producer().cloned(), fees, header.get_gas_limit(), block.get_transactions().clone());
+            if sealed.get_header() != header {
+                return Err(anyhow::anyhow!("Block {}: stored header does not match the replayed block", height));
+            }
+            self.state.check_supply().map_err(|err| anyhow::anyhow!("Block {}: {}", height, err))?;
+            self.blockchain.push(block);
+        }
+        println!("Replayed chain up to block {}", self.get_height() - 1);
+        Ok(())
+    }
+
+    /// Walk the chain from the first block and report the first broken link
+    pub fn verify(&self) -> Result<()> {
+        let mut parent_hash = GENESIS_PARENT_HASH.to_string();
+        for (height, block) in self.blockchain.iter().enumerate() {
+            let header = block.get_header();
+            if header.get_height() != height as u64 {
+                return Err(anyhow::anyhow!("Block {}: header records height {}", height, header.get_height()));
+            }
+            if header.get_parent_hash() != &parent_hash {
+                return Err(anyhow::anyhow!("Block {}: parent hash does not match the hash of the previous block", height));
+            }
+            if header.get_transactions_root() != &block.compute_transactions_root() {
+                return Err(anyhow::anyhow!("Block {}: transactions do not match the transactions root", height));
+            }
+            if header.get_hash() != &header.compute_hash() {
+                return Err(anyhow::anyhow!("Block {}: header hash is invalid", height));
+            }
+            parent_hash = header.get_hash().clone();
+        }
+        Ok(())
+    }
+
+}
+
+/// Execution of the block at `height` on a state, shared by the chain and its simulations, which run it quietly on a copy
+struct BlockRun<'a> {
+    state: &'a mut State,
+    height: u64,
+    verbose: bool,
+}
+
+/// Transactions a block run included, their results in the same order, and those it took from the mempool but left out
+struct Included {
+    transactions: Vec<Transaction>,
+    results: Vec<Result<Receipt>>,
+    dropped: Vec<(Transaction, String)>,
+}
+
+impl BlockRun<'_> {
+    fn log(&self, message: &str) {
+        if self.verbose {
+            println!("  {}", message);
+        }
+    }
+
+    /// Execute the transactions of the block in submission order, leaving the overflow for the next block.
+    /// Failed transactions are included too so that their receipts point into the block.
+    fn include(&mut self, mempool: &mut Mempool, config: &Config) -> Included {
+        let mut included = Included { transactions: Vec::new(), results: Vec::new(), dropped: Vec::new() };
+        let mut gas = config.block_gas;
+        // Scheduled payments due in this block come first, as far as the block limits allow;
+        // the others stay due and go first in the next block, without moving the heights of the payments after them
+        for payment in self.state.due_payments(self.height) {
+            let transaction = Transaction::ScheduledPayment(payment);
+            if included.transactions.len() >= config.block_transactions || transaction.gas() > gas {
+                self.log(&format!("{} - Deferred to the next block", transaction));
+                continue;
+            }
+            gas -= transaction.gas();
+            included.results.push(self.execute(&transaction));
+            included.transactions.push(transaction);
+        }
+        // Mempool transactions share what the scheduled payments left of the block limits
+        for pending in mempool.take(config.block_transactions - included.transactions.len(), gas) {
+            // A transaction its signer no longer authorizes, for instance after the transaction creating the signer failed,
+            // is left out of the block rather than recorded there for free
+            if let Some(Err(err)) = pending.transaction.signed().map(|signed| authorize(self.state, signed)) {
+                self.log(&format!("{} - Dropped: {}", pending.transaction, err));
+                included.dropped.push((pending.transaction, err.to_string()));
+                continue;
+            }
+            included.results.push(self.execute(&pending.transaction));
+            included.transactions.push(pending.transaction);
+        }
+        included
+    }
+
+    /// Apply a transaction of the block
+    fn execute(&mut self, transaction: &Transaction) -> Result<Receipt> {
+        let result = apply(self.state, transaction, self.height);
+        match &result {
+            Ok(receipt) => self.log(&format!("{} - {}", transaction, receipt.message)),
+            Err(err) => self.log(&format!("{} - Failed: {}", transaction, err)),
+        }
+        result
+    }
+
+    /// Bookkeeping once every transaction of the block has been executed, returning the fees of the block
+    fn finish(&mut self, producer: Option<&String>, transactions: &[Transaction]) -> u128 {
+        self.release_escrows();
+        let payments: Vec<&ScheduledPayment> = transactions
+            .iter()
+            .filter_map(|transaction| match transaction {
+                Transaction::ScheduledPayment(payment) => Some(payment),
+                _ => None,
+            })
+            .collect();
+        for (id, policy) in self.state.settle_missed_payments(self.height, &payments) {
+            match policy {
+                FailurePolicy::Skip => self.log(&format!("Schedule {} skipped its payment", id)),
+                FailurePolicy::Cancel => self.log(&format!("Schedule {} cancelled after a failed payment", id)),
+            }
+        }
+        for proposal in self.state.take_expired_proposals(self.height) {
+            self.log(&format!("Proposal {} expired with {} approvals", proposal.get_id(), proposal.get_approvals().len()));
+        }
+        self.reward_producer(producer)
+    }
+
+    /// Credit the receivers of the escrows due at this block, refunding the sender of an escrow its receiver cannot take.
+    /// An escrow neither of them can take stays held and is tried again at the next block.
+    fn release_escrows(&mut self) {
+        for escrow in self.state.due_escrows(self.height) {
+            let (asset, amount) = (escrow.get_asset(), escrow.get_amount());
+            let receiver = self.state.get_account_mut(escrow.get_receiver_id()).unwrap();
+            match receiver.deposit(asset, amount) {
+                Ok(()) => self.log(&format!("Escrow {} released: {}{} to {}", escrow.get_id(), amount, asset, escrow.get_receiver_id())),
+                Err(err) => {
+                    let sender = self.state.get_account_mut(escrow.get_sender_id()).unwrap();
+                    match sender.deposit(asset, amount) {
+                        Ok(()) => self.log(&format!("Escrow {} refunded to {}: {}", escrow.get_id(), escrow.get_sender_id(), err)),
+                        Err(refund_err) => {
+                            self.log(&format!("Escrow {} held until the next block: {}; {}", escrow.get_id(), err, refund_err));
+                            continue;
+                        }
+                    }
+                }
+            }
+            self.state.remove_escrow(escrow.get_id());
+        }
+    }
+
+    /// Credit the fees collected in the block to its producer, returning their total
+    fn reward_producer(&mut self, producer: Option<&String>) -> u128 {
+        let fees = self.state.take_fees();
+        if fees == 0 {
+            return fees;
+        }
+        let credited = match producer.and_then(|producer| self.state.get_account_mut(producer)) {
+            Some(account) => match account.deposit(NATIVE_ASSET, fees) {
+                Ok(()) => {
+                    let message = format!("{} collected {}{} of fees", account.get_id(), fees, NATIVE_ASSET);
+                    self.log(&message);
+                    true
+                }
+                Err(err) => {
+                    self.log(&err.to_string());
+                    false
+                }
+            },
+            None => false,
+        };
+        if !credited {
+            self.log(&format!("Burned {}{} of fees without a producer account", fees, NATIVE_ASSET));
+            if let Err(err) = self.state.burn_supply(NATIVE_ASSET, fees) {
+                self.log(&err.to_string());
+            }
+        }
+        fees
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use ed25519_dalek::SigningKey;
+    use crate::model::{generate_secret_key, public_key_hex, ManualClock};
+    use super::*;
+
+    const BLOCK_TIME: u64 = 10_000;
+
+    fn new_chain(config: Config) -> (Blockchain, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(0));
+        let chain = Blockchain::new(config, clock.clone()).unwrap();
+        (chain, clock)
+    }
+
+    /// Move the clock to the next slot and mint its block
+    fn mint_next(chain: &mut Blockchain, clock: &ManualClock) {
+        clock.advance(chain.time_to_next_block());
+        chain.mint();
+    }
+
+    /// Create alice with 100b and bob with nothing in the next block, returning alice's key
+    fn create_accounts(chain: &mut Blockchain, clock: &ManualClock) -> SigningKey {
+        let alice = generate_secret_key();
+        chain.create_account("alice".to_string(), 100, public_key_hex(&alice)).unwrap();
+        chain.create_account("bob".to_string(), 0, public_key_hex(&generate_secret_key())).unwrap();
+        mint_next(chain, clock);
+        alice
+    }
+
+    fn transfer(key: &SigningKey, amount: u128, fee: u128, nonce: u64) -> Transfer {
+        Transfer::new("alice".to_string(), "bob".to_string(), amount, nonce).with_fee(fee).sign(key)
+    }
+
+    fn balance(chain: &Blockchain, id: &str) -> u128 {
+        chain.get_balance(&id.to_string(), NATIVE_ASSET).unwrap()
+    }
+
+    #[test]
+    fn blocks_are_minted_on_slot_boundaries() {
+        let (mut chain, clock) = new_chain(Config::default());
+        chain.mint();
+        assert_eq!(chain.get_height(), 0);
+
+        clock.advance(Duration::from_millis(BLOCK_TIME - 1));
+        chain.mint();
+        assert_eq!(chain.get_height(), 0);
+        assert_eq!(chain.time_to_next_block(), Duration::from_millis(1));
+
+        clock.advance(Duration::from_millis(1));
+        chain.mint();
+        assert_eq!(chain.get_height(), 1);
+        assert_eq!(chain.get_block(0).unwrap().get_header().get_timestamp(), BLOCK_TIME);
+
+        // Jumping over several slots mints a single block, stamped with the slot it was due at
+        clock.advance(Duration::from_millis(2 * BLOCK_TIME + BLOCK_TIME / 2));
+        chain.mint();
+        chain.mint();
+        assert_eq!(chain.get_height(), 2);
+        assert_eq!(chain.get_block(1).unwrap().get_header().get_timestamp(), 2 * BLOCK_TIME);
+        assert_eq!(chain.time_to_next_block(), Duration::from_millis(BLOCK_TIME / 2));
+    }
+
+    #[test]
+    fn fees_are_burned_or_credited_without_breaking_the_supply() {
+        let (mut chain, clock) = new_chain(Config::default());
+        let alice = create_accounts(&mut chain, &clock);
+        chain.add_transfer(transfer(&alice, 10, 5, 0)).unwrap();
+        mint_next(&mut chain, &clock);
+        assert_eq!((balance(&chain, "alice"), balance(&chain, "bob")), (85, 10));
+        assert_eq!(chain.get_supply()[NATIVE_ASSET], 95);
+        assert!(chain.state.check_supply().is_ok());
+
+        let config = Config { producer: Some("bob".to_string()), ..Config::default() };
+        let (mut chain, clock) = new_chain(config);
+        let alice = create_accounts(&mut chain, &clock);
+        chain.add_transfer(transfer(&alice, 10, 5, 0)).unwrap();
+        mint_next(&mut chain, &clock);
+        assert_eq!((balance(&chain, "alice"), balance(&chain, "bob")), (85, 15));
+        assert_eq!(chain.get_supply()[NATIVE_ASSET], 100);
+        assert!(chain.state.check_supply().is_ok());
+    }
+
+    #[test]
+    fn failed_transactions_use_their_nonce_and_pay_their_fee() {
+        let (mut chain, clock) = new_chain(Config::default());
+        let alice = create_accounts(&mut chain, &clock);
+        let failed = chain.add_transfer(transfer(&alice, 200, 5, 0)).unwrap();
+        let next = chain.add_transfer(transfer(&alice, 10, 0, 1)).unwrap();
+        mint_next(&mut chain, &clock);
+        assert_eq!(chain.get_receipt(&failed).unwrap().status, TransactionStatus::Failed);
+        assert_eq!(chain.get_receipt(&next).unwrap().status, TransactionStatus::Success);
+        assert_eq!((balance(&chain, "alice"), balance(&chain, "bob")), (85, 10));
+        assert_eq!(chain.get_next_nonce(&"alice".to_string()).unwrap(), 2);
+        assert_eq!(chain.get_supply()[NATIVE_ASSET], 95);
+    }
+
+    #[test]
+    fn simulations_land_in_the_block_minting_would_give_them() {
+        let config = Config { block_transactions: 1, producer: Some("bob".to_string()), ..Config::default() };
+        let (mut chain, clock) = new_chain(config);
+        let alice = create_accounts(&mut chain, &clock);
+        // Bob's creation did not fit in the block with alice's
+        mint_next(&mut chain, &clock);
+        chain.add_transfer(transfer(&alice, 10, 5, 0)).unwrap();
+        let simulated = Transaction::Transfer(transfer(&alice, 10, 5, 1));
+        let simulation = chain.simulate(&simulated).unwrap();
+        // The pending transfer fills the next block, so the simulated one lands in the block after, fees credited
+        assert_eq!((simulation.receipt.height, simulation.receipt.index), (chain.get_height() + 1, 0));
+        assert_eq!(simulation.balances["alice"].get(NATIVE_ASSET), Some(&70));
+        assert_eq!(simulation.balances["bob"].get(NATIVE_ASSET), Some(&30));
+        assert_eq!(chain.get_mempool().iter().count(), 1);
+    }
+
+    #[test]
+    fn stale_and_replayed_transactions_are_rejected() {
+        let (mut chain, clock) = new_chain(Config::default());
+        let alice = create_accounts(&mut chain, &clock);
+        let first = transfer(&alice, 10, 0, 0);
+        let id = chain.add_transfer(first.clone()).unwrap();
+        assert!(chain.add_transfer(first.clone()).unwrap_err().to_string().contains("already pending"));
+        assert!(chain.add_transfer(transfer(&alice, 20, 0, 0)).unwrap_err().to_string().contains("already used"));
+        assert!(chain.add_transfer(transfer(&alice, 20, 0, 2)).unwrap_err().to_string().contains("skips ahead"));
+        assert!(chain.add_transfer(transfer(&generate_secret_key(), 20, 0, 1)).is_err());
+        mint_next(&mut chain, &clock);
+
+        assert!(chain.add_transfer(first).unwrap_err().to_string().contains("already confirmed"));
+        assert!(chain.add_transfer(transfer(&alice, 20, 0, 0)).unwrap_err().to_string().contains("already used"));
+        chain.add_transfer(transfer(&alice, 20, 0, 1)).unwrap();
+        mint_next(&mut chain, &clock);
+        assert_eq!(balance(&chain, "bob"), 30);
+        assert_eq!(chain.get_receipt(&id).unwrap().height, 1);
+    }
+
+    #[test]
+    fn only_a_full_mempool_makes_the_chain_unavailable() {
+        let (mut chain, clock) = new_chain(Config { mempool_size: 2, ..Config::default() });
+        let alice = create_accounts(&mut chain, &clock);
+        let invalid = chain.add_transfer(transfer(&alice, 10, 0, 1)).unwrap_err();
+        assert!(invalid.downcast_ref::<Unavailable>().is_none());
+        chain.add_transfer(transfer(&alice, 10, 0, 0)).unwrap();
+        chain.add_transfer(transfer(&alice, 10, 0, 1)).unwrap();
+        let full = chain.add_transfer(transfer(&alice, 10, 0, 2)).unwrap_err();
+        assert!(full.downcast_ref::<Unavailable>().is_some());
+    }
+
+    #[test]
+    fn escrows_can_only_be_cancelled_before_their_release() {
+        let (mut chain, clock) = new_chain(Config::default());
+        let alice = create_accounts(&mut chain, &clock);
+        let escrow = EscrowTransfer::new("alice".to_string(), "bob".to_string(), 10, NATIVE_ASSET.to_string(), 3, 0, 0).sign(&alice);
+        let escrow_id = chain.add_escrow(escrow).unwrap();
+        mint_next(&mut chain, &clock);
+        mint_next(&mut chain, &clock);
+        assert_eq!(balance(&chain, "alice"), 90);
+
+        // Block 3 releases the escrow, so cancelling it there fails and bob is credited
+        let cancel_id = chain.cancel_escrow(EscrowCancel::new("alice".to_string(), escrow_id, 0, 1).sign(&alice)).unwrap();
+        mint_next(&mut chain, &clock);
+        let receipt = chain.get_receipt(&cancel_id).unwrap();
+        assert_eq!((receipt.height, receipt.status), (3, TransactionStatus::Failed));
+        assert_eq!((balance(&chain, "alice"), balance(&chain, "bob")), (90, 10));
+        assert!(chain.get_escrows(None).is_empty());
+    }
+
+    #[test]
+    fn deferred_scheduled_payments_catch_up_on_their_schedule() {
+        let (mut chain, clock) = new_chain(Config { block_transactions: 1, ..Config::default() });
+        let alice = create_accounts(&mut chain, &clock);
+        mint_next(&mut chain, &clock);
+        // Confirmed in blocks 2 and 3, both schedules are first due at block 5
+        let schedule = |interval, nonce| {
+            ScheduleCreate::new("alice".to_string(), "bob".to_string(), 10, NATIVE_ASSET.to_string(), interval, 2, FailurePolicy::Skip, 0, nonce)
+                .sign(&alice)
+        };
+        let every_three = chain.add_schedule(schedule(3, 0)).unwrap();
+        mint_next(&mut chain, &clock);
+        let every_two = chain.add_schedule(schedule(2, 1)).unwrap();
+        for _ in 3..9 {
+            mint_next(&mut chain, &clock);
+        }
+        let paid: Vec<&String> = (5..9)
+            .flat_map(|height| chain.get_block(height).unwrap().get_transactions())
+            .filter_map(|transaction| match transaction {
+                Transaction::ScheduledPayment(payment) => Some(payment.get_schedule_id()),
+                _ => None,
+            })
+            .collect();
+        // Block 5 holds one of the payments and defers the other to block 6; the deferred schedule
+        // makes its second payment on time, at block 7 or 8, without waiting an extra block
+        let expected = if every_two < every_three {
+            vec![&every_two, &every_three, &every_two, &every_three]
+        } else {
+            vec![&every_three, &every_two, &every_two, &every_three]
+        };
+        assert_eq!(paid, expected);
+        assert!(chain.get_schedules(None).is_empty());
+        assert_eq!((balance(&chain, "alice"), balance(&chain, "bob")), (60, 40));
+    }
+
+    #[test]
+    fn blocks_always_fit_in_a_record_of_the_block_log() {
+        let config = Config { block_transactions: MAX_BLOCK_TRANSACTIONS + 1, ..Config::default() };
+        assert!(Blockchain::new(config, Arc::new(ManualClock::new(0))).is_err());
+
+        let (mut chain, _) = new_chain(Config::default());
+        let id = "a".repeat(MAX_TRANSACTION_SIZE);
+        assert!(chain.create_account(id, 0, String::new()).unwrap_err().to_string().contains("exceeds the limit"));
+    }
+
+    #[test]
+    fn calls_above_the_gas_limit_are_rejected_when_submitted() {
+        let (mut chain, clock) = new_chain(Config::default());
+        let alice = create_accounts(&mut chain, &clock);
+        let call = ContractCall::new("alice".to_string(), "counter".to_string(), 0, Vec::new(), MAX_CALL_GAS + 1, 0, 0).sign(&alice);
+        assert!(chain.call_contract(call).unwrap_err().to_string().contains("above the maximum"));
+    }
+
+    #[test]
+    fn the_block_log_replays_into_the_same_chain() {
+        let dir = std::env::temp_dir().join(format!("b-chain-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let config = Config { data_dir: Some(dir.clone()), ..Config::default() };
+        let (mut chain, clock) = new_chain(config.clone());
+        let alice = create_accounts(&mut chain, &clock);
+        let id = chain.add_transfer(transfer(&alice, 10, 1, 0)).unwrap();
+        mint_next(&mut chain, &clock);
+        mint_next(&mut chain, &clock);
+        let last_hash = chain.get_last_hash();
+        drop(chain);
+
+        clock.advance(Duration::from_millis(BLOCK_TIME / 2));
+        let mut replayed = Blockchain::new(config, clock.clone()).unwrap();
+        assert_eq!(replayed.get_height(), 3);
+        assert_eq!(replayed.get_last_hash(), last_hash);
+        assert_eq!((balance(&replayed, "alice"), balance(&replayed, "bob")), (89, 10));
+        assert_eq!(replayed.get_receipt(&id).unwrap().height, 1);
+        assert_eq!(replayed.get_next_nonce(&"alice".to_string()).unwrap(), 1);
+        // The slot grid carries on from the first stored block
+        assert_eq!(replayed.time_to_next_block(), Duration::from_millis(BLOCK_TIME / 2));
+        mint_next(&mut replayed, &clock);
+        assert_eq!(replayed.get_block(3).unwrap().get_header().get_timestamp(), 4 * BLOCK_TIME);
+        assert!(replayed.verify().is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn verify_rejects_a_duplicated_last_transaction() {
+        let (mut chain, clock) = new_chain(Config::default());
+        let alice = create_accounts(&mut chain, &clock);
+        for nonce in 0..3 {
+            chain.add_transfer(transfer(&alice, 1, 0, nonce)).unwrap();
+        }
+        mint_next(&mut chain, &clock);
+        assert!(chain.verify().is_ok());
+
+        // Repeat the last of the three transactions, keeping the header as it was
+        let mut block = serde_json::to_value(chain.blockchain.last().unwrap()).unwrap();
+        let transactions = block["transactions"].as_array_mut().unwrap();
+        assert_eq!(transactions.len(), 3);
+        transactions.push(transactions[2].clone());
+        *chain.blockchain.last_mut().unwrap() = serde_json::from_value(block).unwrap();
+        assert!(chain.verify().unwrap_err().to_string().contains("transactions root"));
+    }
 }
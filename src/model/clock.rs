@@ -0,0 +1,51 @@
+#[cfg(test)]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the time blocks are minted by
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch
+    fn now(&self) -> u64;
+}
+
+/// Clock following the system time
+pub struct SystemClock;
+
+/// Clock standing still until it is advanced, its clones sharing the same time; tests mint blocks with it without waiting
+#[cfg(test)]
+#[derive(Clone)]
+pub struct ManualClock {
+    now: Arc<AtomicU64>,
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+    }
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new(now: u64) -> ManualClock {
+        ManualClock {
+            now: Arc::new(AtomicU64::new(now))
+        }
+    }
+
+    /// Move the clock forward, returning the new time
+    pub fn advance(&self, by: Duration) -> u64 {
+        let by = by.as_millis() as u64;
+        self.now.fetch_add(by, Ordering::SeqCst) + by
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use anyhow::Result;
+
+/// Parameters of a node's chain, fixed when the node starts
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of transactions waiting in the mempool
+    pub mempool_size: usize,
+    /// Maximum number of transactions included in a single block, at most `MAX_BLOCK_TRANSACTIONS`
+    pub block_transactions: usize,
+    /// Time between two blocks; blocks are minted on the multiples of it since the first block
+    pub block_time: Duration,
+    /// Leave slots without transactions empty instead of minting empty blocks
+    pub skip_empty: bool,
+    /// Gas the transactions of a single block may use, see `Transaction::gas`
+    pub block_gas: u64,
+    /// Directory holding the block log; the chain only lives in memory without it
+    pub data_dir: Option<PathBuf>,
+    /// Account credited with the fees of every block this node mints; fees are burned without it
+    pub producer: Option<String>,
+    /// Lowest fee a transfer must offer to be accepted into the mempool
+    pub min_fee: u128,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            mempool_size: 10_000,
+            block_transactions: 1_000,
+            block_time: Duration::from_secs(10),
+            skip_empty: false,
+            block_gas: 10_000_000,
+            data_dir: None,
+            producer: None,
+            min_fee: 0,
+        }
+    }
+}
+
+/// Parse a duration such as `10s`, `500ms` or `2m`, a bare number counting seconds
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let (number, unit) = match duration.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => duration.split_at(index),
+        None => (duration, "s"),
+    };
+    let number = number.parse::<u64>().map_err(|_| anyhow::anyhow!("Invalid duration {}", duration))?;
+    let duration = match unit {
+        "ms" => Duration::from_millis(number),
+        "s" => Duration::from_secs(number),
+        "m" => Duration::from_secs(number.saturating_mul(60)),
+        _ => return Err(anyhow::anyhow!("Unknown unit {} in duration {}, expected ms, s or m", unit, duration)),
+    };
+    if duration.is_zero() {
+        return Err(anyhow::anyhow!("Duration must be greater than 0"));
+    }
+    Ok(duration)
+}
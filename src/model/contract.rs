@@ -0,0 +1,210 @@
+use std::fmt;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use super::{decode, Op, Signed, SigningPayload, Storage, Value, NATIVE_ASSET};
+
+/// Code attached to a contract account, which has no key of its own, and the storage it keeps between calls
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contract {
+    /// Hex encoded bytecode
+    code: String,
+    storage: Storage,
+}
+
+/// Transaction creating a contract account running the given code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractDeploy {
+    deployer: String,
+    contract: String,
+    /// Hex encoded bytecode
+    code: String,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Transaction running the code of a contract, optionally sending it native coin first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractCall {
+    caller: String,
+    contract: String,
+    value: u128,
+    args: Vec<Value>,
+    /// Most gas the call may use before failing
+    gas_limit: u64,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Decode hex encoded bytecode into instructions
+pub fn decode_code(code: &str) -> Result<Vec<Op>> {
+    let bytes = hex::decode(code).map_err(|_| anyhow::anyhow!("Code is not valid hex"))?;
+    decode(&bytes)
+}
+
+impl Contract {
+    pub fn new(code: String) -> Contract {
+        Contract {
+            code,
+            storage: Storage::new()
+        }
+    }
+
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    pub fn get_storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    pub fn set_storage(&mut self, storage: Storage) {
+        self.storage = storage;
+    }
+}
+
+impl ContractDeploy {
+    pub fn new(deployer: String, contract: String, code: String, fee: u128, nonce: u64) -> ContractDeploy {
+        ContractDeploy {
+            deployer,
+            contract,
+            code,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_deployer_id(&self) -> &String {
+        &self.deployer
+    }
+
+    pub fn get_contract_id(&self) -> &String {
+        &self.contract
+    }
+
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for ContractDeploy {
+    fn get_signer(&self) -> &String {
+        &self.deployer
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("contract_deploy")
+            .string(&self.deployer)
+            .string(&self.contract)
+            .string(&self.code)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for ContractDeploy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Contract deploy: {} deploys {} ({} bytes, nonce {}, fee {}{})", self.deployer, self.contract, self.code.len() / 2, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl ContractCall {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(caller: String, contract: String, value: u128, args: Vec<Value>, gas_limit: u64, fee: u128, nonce: u64) -> ContractCall {
+        ContractCall {
+            caller,
+            contract,
+            value,
+            args,
+            gas_limit,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_caller_id(&self) -> &String {
+        &self.caller
+    }
+
+    pub fn get_contract_id(&self) -> &String {
+        &self.contract
+    }
+
+    pub fn get_value(&self) -> u128 {
+        self.value
+    }
+
+    pub fn get_args(&self) -> &Vec<Value> {
+        &self.args
+    }
+
+    pub fn get_gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for ContractCall {
+    fn get_signer(&self) -> &String {
+        &self.caller
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        let mut payload = SigningPayload::new("contract_call")
+            .string(&self.caller)
+            .string(&self.contract)
+            .u128(self.value)
+            .u64(self.args.len() as u64);
+        for arg in &self.args {
+            payload = match arg {
+                Value::Int(int) => payload.string("int").u128(*int),
+                Value::Str(string) => payload.string("str").string(string),
+            };
+        }
+        payload
+            .u64(self.gas_limit)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for ContractCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args: Vec<String> = self.args.iter().map(Value::to_string).collect();
+        write!(f, "Contract call: {} calls {}({}) with {}{} (gas limit {}, nonce {}, fee {}{})", self.caller, self.contract, args.join(", "), self.value, NATIVE_ASSET, self.gas_limit, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::rngs::OsRng;
+
+/// Identifies the chain in every signed payload, so signatures cannot be replayed on another chain
+pub const CHAIN_ID: &str = "b-local";
+
+/// Canonical byte encoding of a transaction: the message its sender signs.
+/// Fields are appended in a fixed order, strings prefixed with their length and integers big endian.
+pub struct SigningPayload {
+    bytes: Vec<u8>,
+}
+
+impl SigningPayload {
+    pub fn new(kind: &str) -> SigningPayload {
+        SigningPayload { bytes: Vec::new() }.string(CHAIN_ID).string(kind)
+    }
+
+    pub fn string(mut self, value: &str) -> SigningPayload {
+        self.bytes.extend_from_slice(&(value.len() as u64).to_be_bytes());
+        self.bytes.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    pub fn u64(mut self, value: u64) -> SigningPayload {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn u128(mut self, value: u128) -> SigningPayload {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+}
+
+/// A transaction authorized by the signature of one account, which it consumes a nonce of
+pub trait Signed {
+    /// Account whose key signs the transaction and whose nonce it carries
+    fn get_signer(&self) -> &String;
+
+    fn get_nonce(&self) -> u64;
+
+    /// Hex encoded signature over the signing payload
+    fn get_signature(&self) -> &String;
+
+    fn set_signature(&mut self, signature: String);
+
+    fn signing_payload(&self) -> SigningPayload;
+
+    fn sign(mut self, secret_key: &SigningKey) -> Self
+    where
+        Self: Sized,
+    {
+        let signature = sign(secret_key, &self.signing_payload());
+        self.set_signature(signature);
+        self
+    }
+
+    fn with_signature(mut self, signature: String) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_signature(signature);
+        self
+    }
+}
+
+pub fn generate_secret_key() -> SigningKey {
+    SigningKey::generate(&mut OsRng)
+}
+
+pub fn parse_secret_key(secret_key: &str) -> Result<SigningKey> {
+    let bytes: [u8; 32] = hex::decode(secret_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid secret key"))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+pub fn parse_public_key(public_key: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid public key"))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| anyhow::anyhow!("Invalid public key"))
+}
+
+pub fn public_key_hex(secret_key: &SigningKey) -> String {
+    hex::encode(secret_key.verifying_key().as_bytes())
+}
+
+pub fn sign(secret_key: &SigningKey, payload: &SigningPayload) -> String {
+    hex::encode(secret_key.sign(&payload.bytes).to_bytes())
+}
+
+pub fn verify_signature(public_key: &str, payload: &SigningPayload, signature: &str) -> Result<()> {
+    let public_key = parse_public_key(public_key)?;
+    let signature: [u8; 64] = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid signature"))?;
+    public_key
+        .verify(&payload.bytes, &Signature::from_bytes(&signature))
+        .map_err(|_| anyhow::anyhow!("Invalid signature"))
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::{Signed, SigningPayload, NATIVE_ASSET};
+
+/// Funds debited from the sender and held until the receiver is credited at `release_height`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Escrow {
+    /// ID of the transaction that created the escrow
+    id: String,
+    sender: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+    release_height: u64,
+}
+
+/// Transaction moving funds into escrow
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowTransfer {
+    sender: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+    release_height: u64,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Transaction by which the sender of an escrow takes its funds back before they are released
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowCancel {
+    sender: String,
+    escrow: String,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+impl Escrow {
+    pub fn new(id: String, transfer: &EscrowTransfer) -> Escrow {
+        Escrow {
+            id,
+            sender: transfer.sender.clone(),
+            receiver: transfer.receiver.clone(),
+            amount: transfer.amount,
+            asset: transfer.asset.clone(),
+            release_height: transfer.release_height
+        }
+    }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_release_height(&self) -> u64 {
+        self.release_height
+    }
+}
+
+impl EscrowTransfer {
+    pub fn new(sender: String, receiver: String, amount: u128, asset: String, release_height: u64, fee: u128, nonce: u64) -> EscrowTransfer {
+        EscrowTransfer {
+            sender,
+            receiver,
+            amount,
+            asset,
+            release_height,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_release_height(&self) -> u64 {
+        self.release_height
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for EscrowTransfer {
+    fn get_signer(&self) -> &String {
+        &self.sender
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("escrow")
+            .string(&self.sender)
+            .string(&self.receiver)
+            .u128(self.amount)
+            .string(&self.asset)
+            .u64(self.release_height)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for EscrowTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Escrow: {} -> {} : {}{} at block {} (nonce {}, fee {}{})", self.sender, self.receiver, self.amount, self.asset, self.release_height, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl EscrowCancel {
+    pub fn new(sender: String, escrow: String, fee: u128, nonce: u64) -> EscrowCancel {
+        EscrowCancel {
+            sender,
+            escrow,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_escrow_id(&self) -> &String {
+        &self.escrow
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for EscrowCancel {
+    fn get_signer(&self) -> &String {
+        &self.sender
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("cancel_escrow")
+            .string(&self.sender)
+            .string(&self.escrow)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for EscrowCancel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cancel escrow: {} : {} (nonce {}, fee {}{})", self.sender, self.escrow, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
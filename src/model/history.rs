@@ -0,0 +1,64 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Balance of an account in each asset it holds
+pub type Balances = BTreeMap<String, u128>;
+
+/// Balances of each account, in every asset, at the end of every block that changed them
+#[derive(Debug, Default)]
+pub struct BalanceHistory {
+    balances: HashMap<String, Vec<(u64, Balances)>>,
+}
+
+impl BalanceHistory {
+    pub fn new() -> BalanceHistory {
+        BalanceHistory::default()
+    }
+
+    /// Record the balances of an account at the end of block `height`; blocks are recorded in order
+    pub fn record(&mut self, id: &str, height: u64, balances: Balances) {
+        self.balances.entry(id.to_string()).or_default().push((height, balances));
+    }
+
+    /// Balance of an account in `asset` at the end of block `height`, if the account existed by then
+    pub fn balance_at(&self, id: &String, asset: &str, height: u64) -> Option<u128> {
+        let entries = self.balances.get(id)?;
+        let after = entries.partition_point(|(entry_height, _)| *entry_height <= height);
+        if after == 0 {
+            return None;
+        }
+        Some(entries[after - 1].1.get(asset).copied().unwrap_or(0))
+    }
+}
+
+/// Position in the chain of every transaction that involved each account, oldest first
+#[derive(Debug, Default)]
+pub struct AccountHistory {
+    transactions: HashMap<String, Vec<(u64, usize)>>,
+}
+
+impl AccountHistory {
+    pub fn new() -> AccountHistory {
+        AccountHistory::default()
+    }
+
+    /// Record that transaction `index` of block `height` involved the account
+    pub fn record(&mut self, id: &str, height: u64, index: usize) {
+        self.transactions.entry(id.to_string()).or_default().push((height, index));
+    }
+
+    pub fn contains(&self, id: &String) -> bool {
+        self.transactions.contains_key(id)
+    }
+
+    /// Total number of transactions of the account and the requested page of them
+    pub fn page(&self, id: &String, offset: usize, limit: usize) -> (usize, &[(u64, usize)]) {
+        match self.transactions.get(id) {
+            Some(entries) => {
+                let start = offset.min(entries.len());
+                let end = start.saturating_add(limit).min(entries.len());
+                (entries.len(), &entries[start..end])
+            }
+            None => (0, &[]),
+        }
+    }
+}
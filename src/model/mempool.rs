@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+use anyhow::Result;
+use super::Transaction;
+
+/// A queued transaction and the sequence number it was admitted with
+#[derive(Clone)]
+pub struct PendingTransaction {
+    pub sequence: u64,
+    pub transaction: Transaction,
+}
+
+/// Transactions waiting to be included in a block.
+///
+/// Transactions are executed first-in first-out. Each one is stamped with a
+/// sequence number when it is admitted; since the node admits requests one at
+/// a time, in the order it accepts connections, that sequence is the only
+/// ordering key and two transactions can never tie.
+#[derive(Clone)]
+pub struct Mempool {
+    queue: VecDeque<PendingTransaction>,
+    next_sequence: u64,
+    max_size: usize,
+}
+
+impl Mempool {
+    pub fn new(max_size: usize) -> Mempool {
+        Mempool {
+            queue: VecDeque::new(),
+            next_sequence: 0,
+            max_size,
+        }
+    }
+
+    /// Admit a transaction at the back of the queue.
+    /// A full mempool rejects the transaction.
+    pub fn push(&mut self, transaction: Transaction) -> Result<()> {
+        if self.queue.len() >= self.max_size {
+            return Err(anyhow::anyhow!("Mempool is full ({} transactions pending), try again after the next block", self.max_size));
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.queue.push_back(PendingTransaction {
+            sequence,
+            transaction,
+        });
+        Ok(())
+    }
+
+    /// Remove up to `limit` transactions from the front of the queue, stopping at the first one whose gas
+    /// would exceed `gas`; the rest wait for the next block in the same order
+    pub fn take(&mut self, limit: usize, gas: u64) -> Vec<PendingTransaction> {
+        let mut remaining = gas;
+        let mut count = 0;
+        for pending in self.queue.iter().take(limit) {
+            match remaining.checked_sub(pending.transaction.gas()) {
+                Some(left) => remaining = left,
+                None => break,
+            }
+            count += 1;
+        }
+        self.queue.drain(..count).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Position of a pending transaction in the queue
+    pub fn position(&self, id: &String) -> Option<usize> {
+        self.queue.iter().position(|pending| &pending.transaction.hash() == id)
+    }
+
+    /// Pending transactions in execution order
+    pub fn iter(&self) -> impl Iterator<Item = &PendingTransaction> {
+        self.queue.iter()
+    }
+}
@@ -1,8 +1,40 @@
 
+mod allowance;
+mod asset;
+mod batch;
 mod block;
 mod account;
 mod chain;
+mod clock;
+mod config;
+mod contract;
+mod crypto;
+mod escrow;
+mod history;
+mod mempool;
+mod multisig;
+mod schedule;
+mod state;
+mod store;
+mod transaction;
+mod vm;
 
+pub use allowance::*;
+pub use asset::*;
+pub use batch::*;
 pub use block::*;
 pub use account::*;
 pub use chain::*;
+pub use clock::*;
+pub use config::*;
+pub use contract::*;
+pub use crypto::*;
+pub use escrow::*;
+pub use history::*;
+pub use mempool::*;
+pub use multisig::*;
+pub use schedule::*;
+pub use state::*;
+pub use store::*;
+pub use transaction::*;
+pub use vm::*;
@@ -0,0 +1,341 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::{Signed, SigningPayload, NATIVE_ASSET};
+
+/// Members of a multisig account and how many of them must approve a withdrawal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Multisig {
+    members: Vec<String>,
+    threshold: usize,
+}
+
+/// Transaction creating a multisig account, signed and paid for by its creator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigCreate {
+    creator: String,
+    account: String,
+    members: Vec<String>,
+    threshold: usize,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Withdrawal from a multisig account waiting for the approval of its members
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proposal {
+    /// ID of the transaction that proposed the withdrawal
+    id: String,
+    account: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+    approvals: BTreeSet<String>,
+    /// Height of the last block in which the proposal can still be approved
+    expiry_height: u64,
+}
+
+/// Transaction by which a member proposes a withdrawal, counting as its approval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigProposal {
+    proposer: String,
+    account: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+    /// Number of blocks the proposal stays open for
+    expires_in: u64,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Transaction by which a member approves a pending withdrawal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigApproval {
+    member: String,
+    account: String,
+    proposal: String,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+impl Multisig {
+    pub fn new(members: Vec<String>, threshold: usize) -> Multisig {
+        Multisig {
+            members,
+            threshold
+        }
+    }
+
+    pub fn get_threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn is_member(&self, id: &String) -> bool {
+        self.members.contains(id)
+    }
+}
+
+impl Proposal {
+    pub fn new(id: String, proposal: &MultisigProposal, height: u64) -> Proposal {
+        Proposal {
+            id,
+            account: proposal.account.clone(),
+            receiver: proposal.receiver.clone(),
+            amount: proposal.amount,
+            asset: proposal.asset.clone(),
+            approvals: BTreeSet::from([proposal.proposer.clone()]),
+            expiry_height: height.saturating_add(proposal.expires_in)
+        }
+    }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn get_account_id(&self) -> &String {
+        &self.account
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_approvals(&self) -> &BTreeSet<String> {
+        &self.approvals
+    }
+
+    pub fn get_expiry_height(&self) -> u64 {
+        self.expiry_height
+    }
+
+    pub fn approve(&mut self, member: String) {
+        self.approvals.insert(member);
+    }
+}
+
+impl MultisigProposal {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(proposer: String, account: String, receiver: String, amount: u128, asset: String, expires_in: u64, fee: u128, nonce: u64) -> MultisigProposal {
+        MultisigProposal {
+            proposer,
+            account,
+            receiver,
+            amount,
+            asset,
+            expires_in,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_proposer_id(&self) -> &String {
+        &self.proposer
+    }
+
+    pub fn get_account_id(&self) -> &String {
+        &self.account
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_expires_in(&self) -> u64 {
+        self.expires_in
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for MultisigProposal {
+    fn get_signer(&self) -> &String {
+        &self.proposer
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("multisig_proposal")
+            .string(&self.proposer)
+            .string(&self.account)
+            .string(&self.receiver)
+            .u128(self.amount)
+            .string(&self.asset)
+            .u64(self.expires_in)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for MultisigProposal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Multisig proposal: {} proposes {} -> {} : {}{} (nonce {}, fee {}{})", self.proposer, self.account, self.receiver, self.amount, self.asset, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl MultisigCreate {
+    pub fn new(creator: String, account: String, members: Vec<String>, threshold: usize, fee: u128, nonce: u64) -> MultisigCreate {
+        MultisigCreate {
+            creator,
+            account,
+            members,
+            threshold,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_creator_id(&self) -> &String {
+        &self.creator
+    }
+
+    pub fn get_account_id(&self) -> &String {
+        &self.account
+    }
+
+    pub fn get_members(&self) -> &Vec<String> {
+        &self.members
+    }
+
+    pub fn get_threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for MultisigCreate {
+    fn get_signer(&self) -> &String {
+        &self.creator
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        let mut payload = SigningPayload::new("create_multisig")
+            .string(&self.creator)
+            .string(&self.account)
+            .u64(self.members.len() as u64);
+        for member in &self.members {
+            payload = payload.string(member);
+        }
+        payload
+            .u64(self.threshold as u64)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for MultisigCreate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Create multisig: {} creates {} : {} of {} (nonce {}, fee {}{})", self.creator, self.account, self.threshold, self.members.join(", "), self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl MultisigApproval {
+    pub fn new(member: String, account: String, proposal: String, fee: u128, nonce: u64) -> MultisigApproval {
+        MultisigApproval {
+            member,
+            account,
+            proposal,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_member_id(&self) -> &String {
+        &self.member
+    }
+
+    pub fn get_account_id(&self) -> &String {
+        &self.account
+    }
+
+    pub fn get_proposal_id(&self) -> &String {
+        &self.proposal
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for MultisigApproval {
+    fn get_signer(&self) -> &String {
+        &self.member
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("multisig_approval")
+            .string(&self.member)
+            .string(&self.account)
+            .string(&self.proposal)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for MultisigApproval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Multisig approval: {} approves {} on {} (nonce {}, fee {}{})", self.member, self.proposal, self.account, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::{Signed, SigningPayload, NATIVE_ASSET};
+
+/// What happens to a schedule when the sender cannot afford a payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// The payment is counted as made and the schedule carries on
+    Skip,
+    /// The remaining payments are dropped
+    Cancel,
+}
+
+/// Recurring payment from one account to another, made by the minter every `interval` blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    /// ID of the transaction that created the schedule
+    id: String,
+    sender: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+    interval: u64,
+    on_failure: FailurePolicy,
+    /// Index of the next payment, starting at 0
+    next_payment: u64,
+    /// Height of the block the next payment is made in
+    next_height: u64,
+    remaining: u64,
+}
+
+/// Transaction registering a schedule, its first payment made `interval` blocks later
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleCreate {
+    sender: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+    interval: u64,
+    count: u64,
+    on_failure: FailurePolicy,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Transaction by which the sender of a schedule drops its remaining payments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleCancel {
+    sender: String,
+    schedule: String,
+    fee: u128,
+    nonce: u64,
+    signature: String,
+}
+
+/// Payment of a schedule, added by the minter to the block it is due in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledPayment {
+    schedule: String,
+    payment: u64,
+    sender: String,
+    receiver: String,
+    amount: u128,
+    asset: String,
+}
+
+impl FailurePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailurePolicy::Skip => "skip",
+            FailurePolicy::Cancel => "cancel",
+        }
+    }
+
+    pub fn parse(policy: &str) -> Option<FailurePolicy> {
+        match policy {
+            "skip" => Some(FailurePolicy::Skip),
+            "cancel" => Some(FailurePolicy::Cancel),
+            _ => None,
+        }
+    }
+}
+
+impl Schedule {
+    pub fn new(id: String, create: &ScheduleCreate, height: u64) -> Schedule {
+        Schedule {
+            id,
+            sender: create.sender.clone(),
+            receiver: create.receiver.clone(),
+            amount: create.amount,
+            asset: create.asset.clone(),
+            interval: create.interval,
+            on_failure: create.on_failure,
+            next_payment: 0,
+            next_height: height.saturating_add(create.interval),
+            remaining: create.count
+        }
+    }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_on_failure(&self) -> FailurePolicy {
+        self.on_failure
+    }
+
+    pub fn get_next_height(&self) -> u64 {
+        self.next_height
+    }
+
+    /// The payment due next, as the minter adds it to a block
+    pub fn next_payment(&self) -> ScheduledPayment {
+        ScheduledPayment {
+            schedule: self.id.clone(),
+            payment: self.next_payment,
+            sender: self.sender.clone(),
+            receiver: self.receiver.clone(),
+            amount: self.amount,
+            asset: self.asset.clone()
+        }
+    }
+
+    /// Move on to the following payment, returning false once none remain
+    pub fn advance(&mut self) -> bool {
+        self.next_payment += 1;
+        self.next_height = self.next_height.saturating_add(self.interval);
+        self.remaining -= 1;
+        self.remaining > 0
+    }
+}
+
+impl ScheduleCreate {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(sender: String, receiver: String, amount: u128, asset: String, interval: u64, count: u64, on_failure: FailurePolicy, fee: u128, nonce: u64) -> ScheduleCreate {
+        ScheduleCreate {
+            sender,
+            receiver,
+            amount,
+            asset,
+            interval,
+            count,
+            on_failure,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+
+    pub fn get_interval(&self) -> u64 {
+        self.interval
+    }
+
+    pub fn get_count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for ScheduleCreate {
+    fn get_signer(&self) -> &String {
+        &self.sender
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("schedule")
+            .string(&self.sender)
+            .string(&self.receiver)
+            .u128(self.amount)
+            .string(&self.asset)
+            .u64(self.interval)
+            .u64(self.count)
+            .string(self.on_failure.as_str())
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for ScheduleCreate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Schedule: {} -> {} : {}{} every {} blocks, {} times (nonce {}, fee {}{})", self.sender, self.receiver, self.amount, self.asset, self.interval, self.count, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl ScheduleCancel {
+    pub fn new(sender: String, schedule: String, fee: u128, nonce: u64) -> ScheduleCancel {
+        ScheduleCancel {
+            sender,
+            schedule,
+            fee,
+            nonce,
+            signature: String::new()
+        }
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_schedule_id(&self) -> &String {
+        &self.schedule
+    }
+
+    pub fn get_fee(&self) -> u128 {
+        self.fee
+    }
+}
+
+impl Signed for ScheduleCancel {
+    fn get_signer(&self) -> &String {
+        &self.sender
+    }
+
+    fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    fn get_signature(&self) -> &String {
+        &self.signature
+    }
+
+    fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    fn signing_payload(&self) -> SigningPayload {
+        SigningPayload::new("cancel_schedule")
+            .string(&self.sender)
+            .string(&self.schedule)
+            .u128(self.fee)
+            .u64(self.nonce)
+    }
+}
+
+impl fmt::Display for ScheduleCancel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cancel schedule: {} : {} (nonce {}, fee {}{})", self.sender, self.schedule, self.nonce, self.fee, NATIVE_ASSET)
+    }
+}
+
+impl ScheduledPayment {
+    pub fn get_schedule_id(&self) -> &String {
+        &self.schedule
+    }
+
+    pub fn get_sender_id(&self) -> &String {
+        &self.sender
+    }
+
+    pub fn get_receiver_id(&self) -> &String {
+        &self.receiver
+    }
+
+    pub fn get_amount(&self) -> u128 {
+        self.amount
+    }
+
+    pub fn get_asset(&self) -> &String {
+        &self.asset
+    }
+}
+
+impl fmt::Display for ScheduledPayment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Scheduled payment {} of {}: {} -> {} : {}{}", self.payment, self.schedule, self.sender, self.receiver, self.amount, self.asset)
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use anyhow::Result;
+use super::{Account, Asset, Balances, Escrow, FailurePolicy, Proposal, Schedule, ScheduledPayment};
+
+/// Ledger state that transactions are applied to
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    accounts: HashMap<String, Account>,
+    assets: BTreeMap<String, Asset>,
+    /// Amount of each asset in existence, which the balances of all accounts must add up to
+    supply: Balances,
+    /// Funds held until their release height, by ID
+    escrows: BTreeMap<String, Escrow>,
+    /// Recurring payments with payments left, by ID
+    schedules: BTreeMap<String, Schedule>,
+    /// Multisig withdrawals waiting for approvals, by ID
+    proposals: BTreeMap<String, Proposal>,
+    /// Amount each spender may still move out of each owner's account, by owner, spender and asset
+    allowances: BTreeMap<(String, String, String), u128>,
+    touched: HashSet<String>,
+    /// Fees paid since the last call to `take_fees`
+    fees: u128,
+}
+
+impl State {
+    pub fn new() -> State {
+        State::default()
+    }
+
+    pub fn get_account(&self, id: &String) -> Option<&Account> {
+        self.accounts.get(id)
+    }
+
+    pub fn get_account_mut(&mut self, id: &String) -> Option<&mut Account> {
+        if self.accounts.contains_key(id) {
+            self.touched.insert(id.clone());
+        }
+        self.accounts.get_mut(id)
+    }
+
+    pub fn contains_account(&self, id: &String) -> bool {
+        self.accounts.contains_key(id)
+    }
+
+    pub fn insert_account(&mut self, account: Account) {
+        self.touched.insert(account.get_id().clone());
+        self.accounts.insert(account.get_id().clone(), account);
+    }
+
+    pub fn contains_asset(&self, id: &String) -> bool {
+        self.assets.contains_key(id)
+    }
+
+    /// Issued assets, sorted by ID
+    pub fn get_assets(&self) -> impl Iterator<Item = &Asset> {
+        self.assets.values()
+    }
+
+    pub fn insert_asset(&mut self, asset: Asset) {
+        self.assets.insert(asset.get_id().clone(), asset);
+    }
+
+    pub fn get_supply(&self) -> &Balances {
+        &self.supply
+    }
+
+    /// Bring new units of an asset into existence, to be credited to an account
+    pub fn mint_supply(&mut self, asset: &str, amount: u128) -> Result<()> {
+        let supply = self.supply.get(asset).copied().unwrap_or(0);
+        let supply = supply.checked_add(amount).ok_or_else(|| anyhow::anyhow!("Supply of {} would overflow", asset))?;
+        self.supply.insert(asset.to_string(), supply);
+        Ok(())
+    }
+
+    /// Destroy units of an asset that were debited from an account
+    pub fn burn_supply(&mut self, asset: &str, amount: u128) -> Result<()> {
+        let supply = self.supply.get(asset).copied().unwrap_or(0);
+        let supply = supply.checked_sub(amount).ok_or_else(|| anyhow::anyhow!("Burning {}{} exceeds its supply", amount, asset))?;
+        self.supply.insert(asset.to_string(), supply);
+        Ok(())
+    }
+
+    pub fn get_escrow(&self, id: &String) -> Option<&Escrow> {
+        self.escrows.get(id)
+    }
+
+    /// Escrows still holding funds, sorted by ID
+    pub fn get_escrows(&self) -> impl Iterator<Item = &Escrow> {
+        self.escrows.values()
+    }
+
+    pub fn insert_escrow(&mut self, escrow: Escrow) {
+        self.escrows.insert(escrow.get_id().clone(), escrow);
+    }
+
+    pub fn remove_escrow(&mut self, id: &String) -> Option<Escrow> {
+        self.escrows.remove(id)
+    }
+
+    /// Escrows due for release by the end of block `height`, sorted by ID
+    pub fn due_escrows(&self, height: u64) -> Vec<Escrow> {
+        self.escrows
+            .values()
+            .filter(|escrow| escrow.get_release_height() <= height)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_schedule(&self, id: &String) -> Option<&Schedule> {
+        self.schedules.get(id)
+    }
+
+    pub fn get_schedule_mut(&mut self, id: &String) -> Option<&mut Schedule> {
+        self.schedules.get_mut(id)
+    }
+
+    /// Schedules with payments left, sorted by ID
+    pub fn get_schedules(&self) -> impl Iterator<Item = &Schedule> {
+        self.schedules.values()
+    }
+
+    pub fn insert_schedule(&mut self, schedule: Schedule) {
+        self.schedules.insert(schedule.get_id().clone(), schedule);
+    }
+
+    pub fn remove_schedule(&mut self, id: &String) -> Option<Schedule> {
+        self.schedules.remove(id)
+    }
+
+    /// Payments due by block `height`, sorted by schedule ID, one per schedule.
+    /// A payment deferred from an earlier block is still due at its original height, and the payment after it keeps its own,
+    /// so a schedule behind by a deferral pays one late payment per block until it catches up.
+    pub fn due_payments(&self, height: u64) -> Vec<ScheduledPayment> {
+        self.schedules
+            .values()
+            .filter(|schedule| schedule.get_next_height() <= height)
+            .map(Schedule::next_payment)
+            .collect()
+    }
+
+    /// Apply the failure policy of the schedules whose payment in block `height`, among `payments`, was not made,
+    /// returning their IDs with the policy applied. Payments left out of the block stay due and are not missed.
+    pub fn settle_missed_payments(&mut self, height: u64, payments: &[&ScheduledPayment]) -> Vec<(String, FailurePolicy)> {
+        let mut settled = Vec::new();
+        for payment in payments {
+            let id = payment.get_schedule_id().clone();
+            // A payment that was made moved its schedule on to the next one
+            let schedule = match self.schedules.get_mut(&id) {
+                Some(schedule) if schedule.get_next_height() <= height && &schedule.next_payment() == *payment => schedule,
+                _ => continue,
+            };
+            let policy = schedule.get_on_failure();
+            if policy == FailurePolicy::Cancel || !schedule.advance() {
+                self.schedules.remove(&id);
+            }
+            settled.push((id, policy));
+        }
+        settled
+    }
+
+    pub fn get_proposal(&self, id: &String) -> Option<&Proposal> {
+        self.proposals.get(id)
+    }
+
+    pub fn get_proposal_mut(&mut self, id: &String) -> Option<&mut Proposal> {
+        self.proposals.get_mut(id)
+    }
+
+    /// Proposals waiting for approvals, sorted by ID
+    pub fn get_proposals(&self) -> impl Iterator<Item = &Proposal> {
+        self.proposals.values()
+    }
+
+    pub fn insert_proposal(&mut self, proposal: Proposal) {
+        self.proposals.insert(proposal.get_id().clone(), proposal);
+    }
+
+    pub fn remove_proposal(&mut self, id: &String) -> Option<Proposal> {
+        self.proposals.remove(id)
+    }
+
+    /// Remove the proposals that can no longer be approved after block `height`, sorted by ID
+    pub fn take_expired_proposals(&mut self, height: u64) -> Vec<Proposal> {
+        let expired: Vec<String> = self.proposals
+            .values()
+            .filter(|proposal| proposal.get_expiry_height() <= height)
+            .map(|proposal| proposal.get_id().clone())
+            .collect();
+        expired.iter().filter_map(|id| self.proposals.remove(id)).collect()
+    }
+
+    pub fn get_allowance(&self, owner: &str, spender: &str, asset: &str) -> u128 {
+        self.allowances
+            .get(&(owner.to_string(), spender.to_string(), asset.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn set_allowance(&mut self, owner: &str, spender: &str, asset: &str, amount: u128) {
+        let key = (owner.to_string(), spender.to_string(), asset.to_string());
+        if amount == 0 {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(key, amount);
+        }
+    }
+
+    /// Check that the balances of all accounts, plus the funds held in escrow, add up to the supply of each asset
+    pub fn check_supply(&self) -> Result<()> {
+        let mut held = Balances::new();
+        for account in self.accounts.values() {
+            for (asset, balance) in account.get_balances() {
+                let total = held.entry(asset.clone()).or_insert(0);
+                *total = total.checked_add(*balance).ok_or_else(|| anyhow::anyhow!("Balances of {} overflow", asset))?;
+            }
+        }
+        for escrow in self.escrows.values() {
+            let total = held.entry(escrow.get_asset().clone()).or_insert(0);
+            *total = total.checked_add(escrow.get_amount()).ok_or_else(|| anyhow::anyhow!("Balances of {} overflow", escrow.get_asset()))?;
+        }
+        for asset in held.keys().chain(self.supply.keys()) {
+            let held = held.get(asset).copied().unwrap_or(0);
+            let supply = self.supply.get(asset).copied().unwrap_or(0);
+            if held != supply {
+                return Err(anyhow::anyhow!("Accounts hold {}{} but the supply is {}", held, asset, supply));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn collect_fee(&mut self, fee: u128) {
+        self.fees += fee;
+    }
+
+    /// Fees paid since the last call
+    pub fn take_fees(&mut self) -> u128 {
+        std::mem::take(&mut self.fees)
+    }
+
+    /// Accounts modified since the last call, sorted by ID
+    pub fn take_touched(&mut self) -> Vec<String> {
+        let mut touched: Vec<String> = self.touched.drain().collect();
+        touched.sort();
+        touched
+    }
+}
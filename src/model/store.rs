@@ -0,0 +1,97 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use anyhow::Result;
+use super::{Block, MAX_TRANSACTION_SIZE};
+
+const LOG_FILE: &str = "blocks.log";
+/// Each record starts with the length and the CRC32 of its payload, both big endian
+const RECORD_HEADER_LEN: usize = 8;
+/// Largest payload a record may hold; a longer length field is garbage rather than an interrupted append
+const MAX_RECORD_LEN: usize = 64 * 1024 * 1024;
+/// Most transactions a block may hold for any block to fit in a record, keeping 64 KiB for its header
+/// and a separator byte per transaction
+pub const MAX_BLOCK_TRANSACTIONS: usize = (MAX_RECORD_LEN - 64 * 1024) / (MAX_TRANSACTION_SIZE + 1);
+
+/// Append-only log of minted blocks, one JSON encoded block per record
+pub struct BlockStore {
+    file: File,
+}
+
+impl BlockStore {
+    /// Open the log in `dir`, creating it if needed, and read back every stored block.
+    ///
+    /// A last record cut short, with a plausible length, is what a crash in the middle of
+    /// an append leaves behind: it is cut off the log and the block is minted again. Any
+    /// other damage, including a complete last record failing its checksum, is an error
+    /// and leaves the log untouched.
+    pub fn open(dir: &Path) -> Result<(BlockStore, Vec<Block>)> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE);
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let mut blocks = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            match read_record(&data[offset..]) {
+                Some((payload, record_len)) => {
+                    let block: Block = serde_json::from_slice(payload)
+                        .map_err(|err| anyhow::anyhow!("{}: invalid block at offset {}: {}", path.display(), offset, err))?;
+                    blocks.push(block);
+                    offset += record_len;
+                }
+                None if is_incomplete_record(&data[offset..]) => {
+                    eprintln!("{}: discarding truncated record at offset {}", path.display(), offset);
+                    file.set_len(offset as u64)?;
+                    file.sync_all()?;
+                    break;
+                }
+                None => {
+                    return Err(anyhow::anyhow!("{}: corrupted record at offset {}", path.display(), offset));
+                }
+            }
+        }
+        Ok((BlockStore { file }, blocks))
+    }
+
+    /// Append a block and wait for it to reach the disk
+    pub fn append(&mut self, block: &Block) -> Result<()> {
+        let payload = serde_json::to_vec(block)?;
+        if payload.len() > MAX_RECORD_LEN {
+            return Err(anyhow::anyhow!("Block of {} bytes exceeds the {} bytes a record can hold", payload.len(), MAX_RECORD_LEN));
+        }
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        record.extend_from_slice(&crc32fast::hash(&payload).to_be_bytes());
+        record.extend_from_slice(&payload);
+        self.file.write_all(&record)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Payload and total length of the record at the start of `data`, if it is complete and intact
+fn read_record(data: &[u8]) -> Option<(&[u8], usize)> {
+    if data.len() < RECORD_HEADER_LEN {
+        return None;
+    }
+    let len = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+    let crc = u32::from_be_bytes(data[4..8].try_into().unwrap());
+    let payload = data.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len)?;
+    if crc32fast::hash(payload) != crc {
+        return None;
+    }
+    Some((payload, RECORD_HEADER_LEN + len))
+}
+
+/// Whether the bad record at the start of `data` is an append cut short: the log ends inside it
+/// and its length, when the header made it to the disk, is one the store could have written
+fn is_incomplete_record(data: &[u8]) -> bool {
+    if data.len() < RECORD_HEADER_LEN {
+        return true;
+    }
+    let len = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+    len <= MAX_RECORD_LEN && RECORD_HEADER_LEN + len > data.len()
+}
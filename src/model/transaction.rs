@@ -0,0 +1,679 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use super::{decode_code, execute, parse_public_key, sha256_hex, verify_signature, Account, AllowanceApproval, AllowanceTransfer, Asset, AssetIssue, Balances, BatchTransfer, Contract, ContractCall, ContractDeploy, Env, Escrow, EscrowCancel, EscrowTransfer, Multisig, MultisigApproval, MultisigCreate, MultisigProposal, Proposal, Schedule, ScheduleCancel, ScheduleCreate, ScheduledPayment, Signed, State, Transfer, MAX_CALL_GAS, NATIVE_ASSET};
+
+/// Gas every transaction costs, on top of what its content adds
+pub const TRANSACTION_GAS: u64 = 1_000;
+/// Gas added by each leg of a batch
+pub const BATCH_LEG_GAS: u64 = 500;
+/// Gas added by each member of a multisig account
+pub const MULTISIG_MEMBER_GAS: u64 = 100;
+/// Gas added by each byte of deployed code
+pub const CODE_BYTE_GAS: u64 = 10;
+/// Largest transaction the mempool admits, in bytes of its JSON encoding
+pub const MAX_TRANSACTION_SIZE: usize = 32 * 1024;
+
+/// A state transition waiting to be included in a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transaction {
+    CreateAccount { id: String, balance: u128, public_key: String },
+    Transfer(Transfer),
+    IssueAsset(AssetIssue),
+    Batch(BatchTransfer),
+    Escrow(EscrowTransfer),
+    CancelEscrow(EscrowCancel),
+    Schedule(ScheduleCreate),
+    CancelSchedule(ScheduleCancel),
+    ScheduledPayment(ScheduledPayment),
+    CreateMultisig(MultisigCreate),
+    Propose(MultisigProposal),
+    Approve(MultisigApproval),
+    ApproveAllowance(AllowanceApproval),
+    TransferFrom(AllowanceTransfer),
+    Deploy(ContractDeploy),
+    Call(ContractCall),
+}
+
+/// Outcome of a transaction successfully applied to the state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub message: String,
+}
+
+impl Receipt {
+    pub fn new(message: &str) -> Receipt {
+        Receipt {
+            message: message.to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Success,
+    Failed,
+}
+
+/// Record of a transaction executed in a block, kept by the chain once confirmed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionReceipt {
+    pub id: String,
+    pub status: TransactionStatus,
+    pub reason: Option<String>,
+    pub height: u64,
+    pub index: usize,
+}
+
+impl TransactionReceipt {
+    pub fn new(id: String, result: &Result<Receipt>, height: u64, index: usize) -> TransactionReceipt {
+        let (status, reason) = match result {
+            Ok(_) => (TransactionStatus::Success, None),
+            Err(err) => (TransactionStatus::Failed, Some(err.to_string())),
+        };
+        TransactionReceipt {
+            id,
+            status,
+            reason,
+            height,
+            index,
+        }
+    }
+}
+
+/// Outcome a transaction would have if it were included in the next block
+#[derive(Debug, Clone, Serialize)]
+pub struct Simulation {
+    pub receipt: TransactionReceipt,
+    /// Balances of the accounts the transaction involves, once the block including it is minted
+    pub balances: BTreeMap<String, Balances>,
+}
+
+impl Transaction {
+    /// Accounts the transaction involves, without duplicates
+    pub fn accounts(&self) -> Vec<&String> {
+        let mut accounts = match self {
+            Transaction::CreateAccount { id, .. } => vec![id],
+            Transaction::Transfer(transfer) => vec![transfer.get_sender_id(), transfer.get_receiver_id()],
+            Transaction::IssueAsset(issue) => vec![issue.get_issuer()],
+            Transaction::Batch(batch) => std::iter::once(batch.get_sender_id())
+                .chain(batch.get_legs().iter().map(|leg| leg.get_receiver_id()))
+                .collect(),
+            Transaction::Escrow(escrow) => vec![escrow.get_sender_id(), escrow.get_receiver_id()],
+            Transaction::CancelEscrow(cancel) => vec![cancel.get_sender_id()],
+            Transaction::Schedule(schedule) => vec![schedule.get_sender_id(), schedule.get_receiver_id()],
+            Transaction::CancelSchedule(cancel) => vec![cancel.get_sender_id()],
+            Transaction::ScheduledPayment(payment) => vec![payment.get_sender_id(), payment.get_receiver_id()],
+            Transaction::CreateMultisig(create) => vec![create.get_creator_id(), create.get_account_id()],
+            Transaction::Propose(proposal) => vec![proposal.get_proposer_id(), proposal.get_account_id(), proposal.get_receiver_id()],
+            Transaction::Approve(approval) => vec![approval.get_member_id(), approval.get_account_id()],
+            Transaction::ApproveAllowance(approval) => vec![approval.get_owner_id(), approval.get_spender_id()],
+            Transaction::TransferFrom(transfer) => vec![transfer.get_spender_id(), transfer.get_owner_id(), transfer.get_receiver_id()],
+            Transaction::Deploy(deploy) => vec![deploy.get_deployer_id(), deploy.get_contract_id()],
+            Transaction::Call(call) => vec![call.get_caller_id(), call.get_contract_id()],
+        };
+        let mut seen = HashSet::new();
+        accounts.retain(|id| seen.insert(*id));
+        accounts
+    }
+
+    /// Signed part of the transaction, for the transactions authorized by an account key
+    pub fn signed(&self) -> Option<&dyn Signed> {
+        match self {
+            Transaction::CreateAccount { .. } => None,
+            Transaction::Transfer(transfer) => Some(transfer),
+            Transaction::IssueAsset(issue) => Some(issue),
+            Transaction::Batch(batch) => Some(batch),
+            Transaction::Escrow(escrow) => Some(escrow),
+            Transaction::CancelEscrow(cancel) => Some(cancel),
+            Transaction::Schedule(schedule) => Some(schedule),
+            Transaction::CancelSchedule(cancel) => Some(cancel),
+            Transaction::ScheduledPayment(_) => None,
+            Transaction::CreateMultisig(create) => Some(create),
+            Transaction::Propose(proposal) => Some(proposal),
+            Transaction::Approve(approval) => Some(approval),
+            Transaction::ApproveAllowance(approval) => Some(approval),
+            Transaction::TransferFrom(transfer) => Some(transfer),
+            Transaction::Deploy(deploy) => Some(deploy),
+            Transaction::Call(call) => Some(call),
+        }
+    }
+
+    /// Fee offered to the block producer, carried by every signed transaction
+    pub fn fee(&self) -> Option<u128> {
+        match self {
+            Transaction::Transfer(transfer) => Some(transfer.get_fee()),
+            Transaction::IssueAsset(issue) => Some(issue.get_fee()),
+            Transaction::CancelEscrow(cancel) => Some(cancel.get_fee()),
+            Transaction::CancelSchedule(cancel) => Some(cancel.get_fee()),
+            Transaction::CreateMultisig(create) => Some(create.get_fee()),
+            Transaction::Approve(approval) => Some(approval.get_fee()),
+            Transaction::Batch(batch) => Some(batch.get_fee()),
+            Transaction::Escrow(escrow) => Some(escrow.get_fee()),
+            Transaction::Schedule(schedule) => Some(schedule.get_fee()),
+            Transaction::Propose(proposal) => Some(proposal.get_fee()),
+            Transaction::ApproveAllowance(approval) => Some(approval.get_fee()),
+            Transaction::TransferFrom(transfer) => Some(transfer.get_fee()),
+            Transaction::Deploy(deploy) => Some(deploy.get_fee()),
+            Transaction::Call(call) => Some(call.get_fee()),
+            _ => None,
+        }
+    }
+
+    /// Share of the block gas budget the transaction takes, known before it is executed.
+    /// Contract calls reserve their whole gas limit.
+    pub fn gas(&self) -> u64 {
+        let content = match self {
+            Transaction::Batch(batch) => BATCH_LEG_GAS.saturating_mul(batch.get_legs().len() as u64),
+            Transaction::CreateMultisig(create) => MULTISIG_MEMBER_GAS.saturating_mul(create.get_members().len() as u64),
+            Transaction::Deploy(deploy) => CODE_BYTE_GAS.saturating_mul(deploy.get_code().len() as u64 / 2),
+            Transaction::Call(call) => call.get_gas_limit(),
+            _ => 0,
+        };
+        TRANSACTION_GAS.saturating_add(content)
+    }
+
+    /// The transaction ID, derived from its content only
+    pub fn hash(&self) -> String {
+        sha256_hex(&serde_json::to_vec(self).unwrap())
+    }
+}
+
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transaction::CreateAccount { id, balance, .. } => write!(f, "Create account: {} : {}b", id, balance),
+            Transaction::Transfer(transfer) => write!(f, "{}", transfer),
+            Transaction::IssueAsset(issue) => write!(f, "{}", issue),
+            Transaction::Batch(batch) => write!(f, "{}", batch),
+            Transaction::Escrow(escrow) => write!(f, "{}", escrow),
+            Transaction::CancelEscrow(cancel) => write!(f, "{}", cancel),
+            Transaction::Schedule(schedule) => write!(f, "{}", schedule),
+            Transaction::CancelSchedule(cancel) => write!(f, "{}", cancel),
+            Transaction::ScheduledPayment(payment) => write!(f, "{}", payment),
+            Transaction::CreateMultisig(create) => write!(f, "{}", create),
+            Transaction::Propose(proposal) => write!(f, "{}", proposal),
+            Transaction::Approve(approval) => write!(f, "{}", approval),
+            Transaction::ApproveAllowance(approval) => write!(f, "{}", approval),
+            Transaction::TransferFrom(transfer) => write!(f, "{}", transfer),
+            Transaction::Deploy(deploy) => write!(f, "{}", deploy),
+            Transaction::Call(call) => write!(f, "{}", call),
+        }
+    }
+}
+
+/// Check that an account holds a key of its own and that the transaction carries its signature
+pub fn check_signature(signer: &Account, signed: &dyn Signed) -> Result<()> {
+    if signer.get_multisig().is_some() {
+        return Err(anyhow::anyhow!("{} is a multisig account, its withdrawals must be proposed by its members", signed.get_signer()));
+    } else if signer.get_contract().is_some() {
+        return Err(anyhow::anyhow!("{} is a contract account, its funds can only be moved by its code", signed.get_signer()));
+    }
+    verify_signature(signer.get_public_key(), &signed.signing_payload(), signed.get_signature())
+}
+
+/// Check that the signer of a transaction exists, signed it and carries its next nonce
+pub fn authorize(state: &State, signed: &dyn Signed) -> Result<()> {
+    let signer = state
+        .get_account(signed.get_signer())
+        .ok_or_else(|| anyhow::anyhow!("Account {} not found", signed.get_signer()))?;
+    check_signature(signer, signed)?;
+    signer.check_nonce(signed.get_nonce())
+}
+
+/// Check that an account can pay `amount` of `asset` plus a fee, which is paid in the native coin
+fn check_funds(account: &Account, asset: &str, amount: u128, fee: u128) -> Result<()> {
+    let funded = if asset == NATIVE_ASSET {
+        amount.checked_add(fee).is_some_and(|total| account.get_balance(asset) >= total)
+    } else {
+        account.get_balance(asset) >= amount && account.get_balance(NATIVE_ASSET) >= fee
+    };
+    if !funded {
+        return Err(anyhow::anyhow!("Insufficient funds"));
+    }
+    Ok(())
+}
+
+/// Move funds between two accounts of the state, for the transactions that do not debit their signer
+fn move_funds(state: &mut State, sender: &String, receiver: &String, asset: &String, amount: u128) -> Result<()> {
+    if !state.contains_account(receiver) {
+        return Err(anyhow::anyhow!("Account {} not found", receiver));
+    } else if amount == 0 {
+        return Err(anyhow::anyhow!("Transfer amount must be greater than 0"));
+    } else if asset != NATIVE_ASSET && !state.contains_asset(asset) {
+        return Err(anyhow::anyhow!("Asset {} not found", asset));
+    }
+    state.get_account_mut(sender).unwrap().withdraw(asset, amount)?;
+    state.get_account_mut(receiver).unwrap().deposit(asset, amount)
+}
+
+/// Apply a transaction of the block at `height` to the state.
+/// A failed transaction leaves the state untouched, except that its signer still uses its nonce and pays its fee.
+pub fn apply(state: &mut State, transaction: &Transaction, height: u64) -> Result<Receipt> {
+    let result = apply_transaction(state, transaction, height);
+    if result.is_err() {
+        charge_failure(state, transaction);
+    }
+    result
+}
+
+/// Charge the signer of a failed transaction, if it authorized it, its nonce and as much of the fee as its native balance covers
+fn charge_failure(state: &mut State, transaction: &Transaction) {
+    let signed = match transaction.signed() {
+        Some(signed) if authorize(state, signed).is_ok() => signed,
+        _ => return,
+    };
+    let signer = state.get_account_mut(signed.get_signer()).unwrap();
+    let fee = transaction.fee().unwrap_or_default().min(signer.get_balance(NATIVE_ASSET));
+    // Neither can fail, the nonce was just checked and the fee is within the balance
+    let _ = signer.use_nonce(signed.get_nonce());
+    let _ = signer.withdraw(NATIVE_ASSET, fee);
+    state.collect_fee(fee);
+}
+
+fn apply_transaction(state: &mut State, transaction: &Transaction, height: u64) -> Result<Receipt> {
+    match transaction {
+        Transaction::CreateAccount { id, balance, public_key } => {
+            if state.contains_account(id) {
+                return Err(anyhow::anyhow!("Account already exists"));
+            }
+            parse_public_key(public_key)?;
+            // Starting balances are new money
+            state.mint_supply(NATIVE_ASSET, *balance)?;
+            state.insert_account(Account::new(id.clone(), *balance, public_key.clone()));
+            Ok(Receipt::new("Account created"))
+        }
+        Transaction::Transfer(transfer) => {
+            let sender = transfer.get_sender_id();
+            let receiver = transfer.get_receiver_id();
+            let amount = *transfer.get_amount();
+            let asset = transfer.get_asset();
+            if !state.contains_account(sender) {
+                return Err(anyhow::anyhow!("Account {} not found", sender));
+            } else if !state.contains_account(receiver) {
+                return Err(anyhow::anyhow!("Account {} not found", receiver));
+            } else if amount == 0 {
+                return Err(anyhow::anyhow!("Transfer amount must be greater than 0"));
+            } else if asset != NATIVE_ASSET && !state.contains_asset(asset) {
+                return Err(anyhow::anyhow!("Asset {} not found", asset));
+            }
+
+            authorize(state, transfer)?;
+            let fee = transfer.get_fee();
+            check_funds(state.get_account(sender).unwrap(), asset, amount, fee)?;
+            let sender_account = state.get_account_mut(sender).unwrap();
+            sender_account.use_nonce(transfer.get_nonce())?;
+            sender_account.withdraw(NATIVE_ASSET, fee)?;
+            sender_account.withdraw(asset, amount)?;
+            state.get_account_mut(receiver).unwrap().deposit(asset, amount)?;
+            state.collect_fee(fee);
+            Ok(Receipt::new("Transfer complete"))
+        }
+        Transaction::IssueAsset(issue) => {
+            let issuer = issue.get_issuer();
+            let asset = issue.get_asset();
+            if asset.is_empty() {
+                return Err(anyhow::anyhow!("Asset ID must not be empty"));
+            } else if asset == NATIVE_ASSET || state.contains_asset(asset) {
+                return Err(anyhow::anyhow!("Asset {} already exists", asset));
+            } else if issue.get_supply() == 0 {
+                return Err(anyhow::anyhow!("Asset supply must be greater than 0"));
+            }
+
+            authorize(state, issue)?;
+            check_funds(state.get_account(issuer).unwrap(), NATIVE_ASSET, 0, issue.get_fee())?;
+            state.mint_supply(asset, issue.get_supply())?;
+            let issuer_account = state.get_account_mut(issuer).unwrap();
+            issuer_account.use_nonce(issue.get_nonce())?;
+            issuer_account.withdraw(NATIVE_ASSET, issue.get_fee())?;
+            issuer_account.deposit(asset, issue.get_supply())?;
+            state.collect_fee(issue.get_fee());
+            state.insert_asset(Asset::new(asset.clone(), issuer.clone(), issue.get_supply()));
+            Ok(Receipt::new("Asset issued"))
+        }
+        Transaction::Batch(batch) => {
+            let sender = batch.get_sender_id();
+            if batch.get_legs().is_empty() {
+                return Err(anyhow::anyhow!("Batch must have at least one leg"));
+            }
+
+            authorize(state, batch)?;
+            // Execute the legs on a copy of the state, kept only if every one of them succeeds
+            let mut scratch = state.clone();
+            let sender_account = scratch.get_account_mut(sender).unwrap();
+            sender_account.use_nonce(batch.get_nonce())?;
+            sender_account.withdraw(NATIVE_ASSET, batch.get_fee())?;
+            scratch.collect_fee(batch.get_fee());
+            for (index, leg) in batch.get_legs().iter().enumerate() {
+                move_funds(&mut scratch, sender, leg.get_receiver_id(), leg.get_asset(), leg.get_amount())
+                    .map_err(|err| anyhow::anyhow!("Leg {}: {}", index, err))?;
+            }
+            *state = scratch;
+            Ok(Receipt::new("Batch complete"))
+        }
+        Transaction::Escrow(escrow) => {
+            let sender = escrow.get_sender_id();
+            let receiver = escrow.get_receiver_id();
+            let amount = escrow.get_amount();
+            let asset = escrow.get_asset();
+            if !state.contains_account(sender) {
+                return Err(anyhow::anyhow!("Account {} not found", sender));
+            } else if !state.contains_account(receiver) {
+                return Err(anyhow::anyhow!("Account {} not found", receiver));
+            } else if amount == 0 {
+                return Err(anyhow::anyhow!("Transfer amount must be greater than 0"));
+            } else if asset != NATIVE_ASSET && !state.contains_asset(asset) {
+                return Err(anyhow::anyhow!("Asset {} not found", asset));
+            } else if escrow.get_release_height() <= height {
+                return Err(anyhow::anyhow!("Release height {} must be after block {}", escrow.get_release_height(), height));
+            }
+
+            authorize(state, escrow)?;
+            let fee = escrow.get_fee();
+            check_funds(state.get_account(sender).unwrap(), asset, amount, fee)?;
+            let sender_account = state.get_account_mut(sender).unwrap();
+            sender_account.use_nonce(escrow.get_nonce())?;
+            sender_account.withdraw(NATIVE_ASSET, fee)?;
+            sender_account.withdraw(asset, amount)?;
+            state.collect_fee(fee);
+            state.insert_escrow(Escrow::new(transaction.hash(), escrow));
+            Ok(Receipt::new("Funds held in escrow"))
+        }
+        Transaction::CancelEscrow(cancel) => {
+            let sender = cancel.get_sender_id();
+            let id = cancel.get_escrow_id();
+            let escrow = match state.get_escrow(id) {
+                Some(escrow) if escrow.get_sender_id() != sender => return Err(anyhow::anyhow!("Escrow {} was not sent by {}", id, sender)),
+                // The escrow is released at the end of this block, past the point it can be taken back
+                Some(escrow) if height >= escrow.get_release_height() => {
+                    return Err(anyhow::anyhow!("Escrow {} is released at block {} and can no longer be cancelled", id, escrow.get_release_height()))
+                }
+                Some(escrow) => escrow.clone(),
+                None => return Err(anyhow::anyhow!("Escrow {} not found or already released", id)),
+            };
+
+            authorize(state, cancel)?;
+            check_funds(state.get_account(sender).unwrap(), NATIVE_ASSET, 0, cancel.get_fee())?;
+            let sender_account = state.get_account_mut(sender).unwrap();
+            sender_account.deposit(escrow.get_asset(), escrow.get_amount())?;
+            sender_account.use_nonce(cancel.get_nonce())?;
+            sender_account.withdraw(NATIVE_ASSET, cancel.get_fee())?;
+            state.collect_fee(cancel.get_fee());
+            state.remove_escrow(id);
+            Ok(Receipt::new("Escrow cancelled"))
+        }
+        Transaction::Schedule(schedule) => {
+            let sender = schedule.get_sender_id();
+            let receiver = schedule.get_receiver_id();
+            let asset = schedule.get_asset();
+            if !state.contains_account(sender) {
+                return Err(anyhow::anyhow!("Account {} not found", sender));
+            } else if !state.contains_account(receiver) {
+                return Err(anyhow::anyhow!("Account {} not found", receiver));
+            } else if schedule.get_amount() == 0 {
+                return Err(anyhow::anyhow!("Transfer amount must be greater than 0"));
+            } else if asset != NATIVE_ASSET && !state.contains_asset(asset) {
+                return Err(anyhow::anyhow!("Asset {} not found", asset));
+            } else if schedule.get_interval() == 0 || schedule.get_count() == 0 {
+                return Err(anyhow::anyhow!("Schedule interval and payment count must be greater than 0"));
+            }
+
+            authorize(state, schedule)?;
+            check_funds(state.get_account(sender).unwrap(), NATIVE_ASSET, 0, schedule.get_fee())?;
+            let sender_account = state.get_account_mut(sender).unwrap();
+            sender_account.use_nonce(schedule.get_nonce())?;
+            sender_account.withdraw(NATIVE_ASSET, schedule.get_fee())?;
+            state.collect_fee(schedule.get_fee());
+            state.insert_schedule(Schedule::new(transaction.hash(), schedule, height));
+            Ok(Receipt::new("Schedule created"))
+        }
+        Transaction::CancelSchedule(cancel) => {
+            let sender = cancel.get_sender_id();
+            let id = cancel.get_schedule_id();
+            match state.get_schedule(id) {
+                Some(schedule) if schedule.get_sender_id() == sender => {}
+                Some(_) => return Err(anyhow::anyhow!("Schedule {} was not created by {}", id, sender)),
+                None => return Err(anyhow::anyhow!("Schedule {} not found or already completed", id)),
+            }
+
+            authorize(state, cancel)?;
+            check_funds(state.get_account(sender).unwrap(), NATIVE_ASSET, 0, cancel.get_fee())?;
+            let sender_account = state.get_account_mut(sender).unwrap();
+            sender_account.use_nonce(cancel.get_nonce())?;
+            sender_account.withdraw(NATIVE_ASSET, cancel.get_fee())?;
+            state.collect_fee(cancel.get_fee());
+            state.remove_schedule(id);
+            Ok(Receipt::new("Schedule cancelled"))
+        }
+        Transaction::ScheduledPayment(payment) => {
+            let id = payment.get_schedule_id();
+            match state.get_schedule(id) {
+                Some(schedule) if schedule.get_next_height() <= height && &schedule.next_payment() == payment => {}
+                _ => return Err(anyhow::anyhow!("Payment is not due for schedule {}", id)),
+            }
+
+            move_funds(state, payment.get_sender_id(), payment.get_receiver_id(), payment.get_asset(), payment.get_amount())?;
+            if !state.get_schedule_mut(id).unwrap().advance() {
+                state.remove_schedule(id);
+            }
+            Ok(Receipt::new("Scheduled payment made"))
+        }
+        Transaction::CreateMultisig(create) => {
+            let creator = create.get_creator_id();
+            let id = create.get_account_id();
+            let members = create.get_members();
+            let threshold = create.get_threshold();
+            if state.contains_account(id) {
+                return Err(anyhow::anyhow!("Account already exists"));
+            } else if threshold == 0 || threshold > members.len() {
+                return Err(anyhow::anyhow!("Threshold must be between 1 and the number of members"));
+            }
+            let mut seen = HashSet::new();
+            for member in members {
+                if !seen.insert(member) {
+                    return Err(anyhow::anyhow!("{} is listed twice", member));
+                } else if !state.contains_account(member) {
+                    return Err(anyhow::anyhow!("Account {} not found", member));
+                }
+            }
+
+            authorize(state, create)?;
+            check_funds(state.get_account(creator).unwrap(), NATIVE_ASSET, 0, create.get_fee())?;
+            let creator_account = state.get_account_mut(creator).unwrap();
+            creator_account.use_nonce(create.get_nonce())?;
+            creator_account.withdraw(NATIVE_ASSET, create.get_fee())?;
+            state.collect_fee(create.get_fee());
+            state.insert_account(Account::new_multisig(id.clone(), Multisig::new(members.clone(), threshold)));
+            Ok(Receipt::new("Multisig account created"))
+        }
+        Transaction::Propose(proposal) => {
+            let proposer = proposal.get_proposer_id();
+            let account = proposal.get_account_id();
+            let asset = proposal.get_asset();
+            let multisig = match state.get_account(account).map(Account::get_multisig) {
+                Some(Some(multisig)) => multisig,
+                Some(None) => return Err(anyhow::anyhow!("{} is not a multisig account", account)),
+                None => return Err(anyhow::anyhow!("Account {} not found", account)),
+            };
+            if !multisig.is_member(proposer) {
+                return Err(anyhow::anyhow!("{} is not a member of {}", proposer, account));
+            } else if !state.contains_account(proposal.get_receiver_id()) {
+                return Err(anyhow::anyhow!("Account {} not found", proposal.get_receiver_id()));
+            } else if proposal.get_amount() == 0 {
+                return Err(anyhow::anyhow!("Transfer amount must be greater than 0"));
+            } else if asset != NATIVE_ASSET && !state.contains_asset(asset) {
+                return Err(anyhow::anyhow!("Asset {} not found", asset));
+            } else if proposal.get_expires_in() == 0 {
+                return Err(anyhow::anyhow!("Proposal must stay open for at least one block"));
+            }
+
+            authorize(state, proposal)?;
+            check_funds(state.get_account(proposer).unwrap(), NATIVE_ASSET, 0, proposal.get_fee())?;
+            let pending = Proposal::new(transaction.hash(), proposal, height);
+            // With a threshold of 1 the proposal alone executes the withdrawal
+            let executed = multisig.get_threshold() <= pending.get_approvals().len();
+            if executed {
+                move_funds(state, account, pending.get_receiver_id(), pending.get_asset(), pending.get_amount())?;
+            }
+            let proposer_account = state.get_account_mut(proposer).unwrap();
+            proposer_account.use_nonce(proposal.get_nonce())?;
+            proposer_account.withdraw(NATIVE_ASSET, proposal.get_fee())?;
+            state.collect_fee(proposal.get_fee());
+            if executed {
+                return Ok(Receipt::new("Proposal executed"));
+            }
+            state.insert_proposal(pending);
+            Ok(Receipt::new("Proposal waiting for approvals"))
+        }
+        Transaction::Approve(approval) => {
+            let member = approval.get_member_id();
+            let id = approval.get_proposal_id();
+            let pending = match state.get_proposal(id) {
+                Some(pending) if pending.get_account_id() != approval.get_account_id() => {
+                    return Err(anyhow::anyhow!("Proposal {} is not for {}", id, approval.get_account_id()));
+                }
+                Some(pending) if pending.get_expiry_height() < height => {
+                    return Err(anyhow::anyhow!("Proposal {} expired at block {}", id, pending.get_expiry_height()));
+                }
+                Some(pending) => pending.clone(),
+                None => return Err(anyhow::anyhow!("Proposal {} not found, executed or expired", id)),
+            };
+            let multisig = state.get_account(pending.get_account_id()).unwrap().get_multisig().unwrap();
+            if !multisig.is_member(member) {
+                return Err(anyhow::anyhow!("{} is not a member of {}", member, pending.get_account_id()));
+            } else if pending.get_approvals().contains(member) {
+                return Err(anyhow::anyhow!("{} already approved proposal {}", member, id));
+            }
+
+            authorize(state, approval)?;
+            check_funds(state.get_account(member).unwrap(), NATIVE_ASSET, 0, approval.get_fee())?;
+            let threshold = multisig.get_threshold();
+            let approvals = pending.get_approvals().len() + 1;
+            let executed = approvals >= threshold;
+            if executed {
+                // The withdrawal executes in the block that reaches the threshold
+                move_funds(state, pending.get_account_id(), pending.get_receiver_id(), pending.get_asset(), pending.get_amount())?;
+            }
+            let member_account = state.get_account_mut(member).unwrap();
+            member_account.use_nonce(approval.get_nonce())?;
+            member_account.withdraw(NATIVE_ASSET, approval.get_fee())?;
+            state.collect_fee(approval.get_fee());
+            if executed {
+                state.remove_proposal(id);
+                return Ok(Receipt::new("Proposal approved and executed"));
+            }
+            state.get_proposal_mut(id).unwrap().approve(member.clone());
+            Ok(Receipt::new(&format!("Proposal approved, {} of {} approvals", approvals, threshold)))
+        }
+        Transaction::ApproveAllowance(approval) => {
+            let owner = approval.get_owner_id();
+            let spender = approval.get_spender_id();
+            let asset = approval.get_asset();
+            if !state.contains_account(spender) {
+                return Err(anyhow::anyhow!("Account {} not found", spender));
+            } else if owner == spender {
+                return Err(anyhow::anyhow!("An account cannot be its own spender"));
+            } else if asset != NATIVE_ASSET && !state.contains_asset(asset) {
+                return Err(anyhow::anyhow!("Asset {} not found", asset));
+            }
+
+            authorize(state, approval)?;
+            check_funds(state.get_account(owner).unwrap(), NATIVE_ASSET, 0, approval.get_fee())?;
+            let owner_account = state.get_account_mut(owner).unwrap();
+            owner_account.use_nonce(approval.get_nonce())?;
+            owner_account.withdraw(NATIVE_ASSET, approval.get_fee())?;
+            state.collect_fee(approval.get_fee());
+            state.set_allowance(owner, spender, asset, approval.get_amount());
+            Ok(Receipt::new("Allowance set"))
+        }
+        Transaction::TransferFrom(transfer) => {
+            let spender = transfer.get_spender_id();
+            let owner = transfer.get_owner_id();
+            let asset = transfer.get_asset();
+            let amount = transfer.get_amount();
+            if !state.contains_account(owner) {
+                return Err(anyhow::anyhow!("Account {} not found", owner));
+            } else if owner == spender {
+                return Err(anyhow::anyhow!("An account cannot be its own spender"));
+            }
+
+            authorize(state, transfer)?;
+            let allowance = state.get_allowance(owner, spender, asset);
+            if allowance < amount {
+                return Err(anyhow::anyhow!("Allowance of {} on {} is {}{}", spender, owner, allowance, asset));
+            }
+            check_funds(state.get_account(spender).unwrap(), NATIVE_ASSET, 0, transfer.get_fee())?;
+            move_funds(state, owner, transfer.get_receiver_id(), asset, amount)?;
+            state.set_allowance(owner, spender, asset, allowance - amount);
+            let spender_account = state.get_account_mut(spender).unwrap();
+            spender_account.use_nonce(transfer.get_nonce())?;
+            spender_account.withdraw(NATIVE_ASSET, transfer.get_fee())?;
+            state.collect_fee(transfer.get_fee());
+            Ok(Receipt::new("Transfer complete"))
+        }
+        Transaction::Deploy(deploy) => {
+            let deployer = deploy.get_deployer_id();
+            let contract = deploy.get_contract_id();
+            if state.contains_account(contract) {
+                return Err(anyhow::anyhow!("Account already exists"));
+            }
+            decode_code(deploy.get_code())?;
+
+            authorize(state, deploy)?;
+            check_funds(state.get_account(deployer).unwrap(), NATIVE_ASSET, 0, deploy.get_fee())?;
+            let deployer_account = state.get_account_mut(deployer).unwrap();
+            deployer_account.use_nonce(deploy.get_nonce())?;
+            deployer_account.withdraw(NATIVE_ASSET, deploy.get_fee())?;
+            state.collect_fee(deploy.get_fee());
+            state.insert_account(Account::new_contract(contract.clone(), Contract::new(deploy.get_code().clone())));
+            Ok(Receipt::new("Contract deployed"))
+        }
+        Transaction::Call(call) => {
+            let caller = call.get_caller_id();
+            let contract = call.get_contract_id();
+            let value = call.get_value();
+            let code = match state.get_account(contract).and_then(Account::get_contract) {
+                Some(contract) => decode_code(contract.get_code())?,
+                None => return Err(anyhow::anyhow!("Contract {} not found", contract)),
+            };
+            if call.get_gas_limit() > MAX_CALL_GAS {
+                return Err(anyhow::anyhow!("Gas limit {} is above the maximum of {}", call.get_gas_limit(), MAX_CALL_GAS));
+            }
+
+            authorize(state, call)?;
+            check_funds(state.get_account(caller).unwrap(), NATIVE_ASSET, value, call.get_fee())?;
+            // Run the call on a copy of the state, kept only if the code and the transfers it emits all succeed
+            let mut scratch = state.clone();
+            let caller_account = scratch.get_account_mut(caller).unwrap();
+            caller_account.use_nonce(call.get_nonce())?;
+            caller_account.withdraw(NATIVE_ASSET, call.get_fee())?;
+            scratch.collect_fee(call.get_fee());
+            if value > 0 {
+                move_funds(&mut scratch, caller, contract, &NATIVE_ASSET.to_string(), value)?;
+            }
+            let contract_account = scratch.get_account(contract).unwrap();
+            let env = Env {
+                caller: caller.clone(),
+                value,
+                args: call.get_args().clone(),
+                height,
+                balance: contract_account.get_balance(NATIVE_ASSET),
+            };
+            let storage = contract_account.get_contract().unwrap().get_storage().clone();
+            let execution = execute(&code, storage, &env, call.get_gas_limit())?;
+            for (receiver, amount) in execution.get_transfers() {
+                move_funds(&mut scratch, contract, receiver, &NATIVE_ASSET.to_string(), *amount)?;
+            }
+            scratch
+                .get_account_mut(contract)
+                .and_then(Account::get_contract_mut)
+                .unwrap()
+                .set_storage(execution.get_storage().clone());
+            *state = scratch;
+            Ok(Receipt::new(&format!("Contract executed, {} gas used", execution.get_gas_used())))
+        }
+    }
+}
@@ -0,0 +1,547 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Largest bytecode a contract can be deployed with, in bytes
+pub const MAX_CODE_SIZE: usize = 4096;
+/// Number of values the stack can hold
+pub const MAX_STACK_DEPTH: usize = 256;
+/// Number of storage slots a contract can hold
+pub const MAX_STORAGE_SLOTS: usize = 64;
+/// Longest string a storage slot can hold, in bytes
+pub const MAX_VALUE_SIZE: usize = 256;
+/// Most gas a single call can be given
+pub const MAX_CALL_GAS: u64 = 100_000;
+
+/// Value held on the stack, in storage or passed as a call argument
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Value {
+    Int(u128),
+    Str(String),
+}
+
+/// Storage of a contract, by slot
+pub type Storage = BTreeMap<u64, Value>;
+
+/// Instruction of the VM, jump targets being instruction indexes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Stop,
+    Push(u128),
+    PushStr(String),
+    Pop,
+    Dup,
+    Swap,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Lt,
+    Gt,
+    Not,
+    Jump(u16),
+    JumpIf(u16),
+    Load,
+    Store,
+    Caller,
+    CallValue,
+    Arg,
+    Height,
+    Balance,
+    Transfer,
+    Revert,
+}
+
+/// What a call can see of the chain
+pub struct Env {
+    pub caller: String,
+    /// Amount of native coin sent along with the call, already credited to the contract
+    pub value: u128,
+    pub args: Vec<Value>,
+    pub height: u64,
+    /// Native balance of the contract
+    pub balance: u128,
+}
+
+/// Outcome of a successful call
+#[derive(Debug)]
+pub struct Execution {
+    storage: Storage,
+    /// Native coin sent by the contract, by receiver, in emission order
+    transfers: Vec<(String, u128)>,
+    gas_used: u64,
+}
+
+impl Value {
+    /// Parse a command line argument, as an integer if it is one
+    pub fn parse(arg: &str) -> Value {
+        match arg.parse::<u128>() {
+            Ok(int) => Value::Int(int),
+            Err(_) => Value::Str(arg.to_string()),
+        }
+    }
+
+    fn as_int(&self) -> Result<u128> {
+        match self {
+            Value::Int(int) => Ok(*int),
+            Value::Str(string) => Err(anyhow::anyhow!("Expected an integer, found \"{}\"", string)),
+        }
+    }
+
+    fn as_str(&self) -> Result<&String> {
+        match self {
+            Value::Str(string) => Ok(string),
+            Value::Int(int) => Err(anyhow::anyhow!("Expected a string, found {}", int)),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(int) => write!(f, "{}", int),
+            Value::Str(string) => write!(f, "\"{}\"", string),
+        }
+    }
+}
+
+impl Op {
+    fn opcode(&self) -> u8 {
+        match self {
+            Op::Stop => 0x00,
+            Op::Push(_) => 0x01,
+            Op::PushStr(_) => 0x02,
+            Op::Pop => 0x03,
+            Op::Dup => 0x04,
+            Op::Swap => 0x05,
+            Op::Add => 0x10,
+            Op::Sub => 0x11,
+            Op::Mul => 0x12,
+            Op::Div => 0x13,
+            Op::Mod => 0x14,
+            Op::Eq => 0x20,
+            Op::Lt => 0x21,
+            Op::Gt => 0x22,
+            Op::Not => 0x23,
+            Op::Jump(_) => 0x30,
+            Op::JumpIf(_) => 0x31,
+            Op::Load => 0x40,
+            Op::Store => 0x41,
+            Op::Caller => 0x50,
+            Op::CallValue => 0x51,
+            Op::Arg => 0x52,
+            Op::Height => 0x53,
+            Op::Balance => 0x54,
+            Op::Transfer => 0x60,
+            Op::Revert => 0xff,
+        }
+    }
+
+    /// Instruction without operand written as `mnemonic` in assembly
+    fn from_mnemonic(mnemonic: &str) -> Option<Op> {
+        let op = match mnemonic {
+            "STOP" => Op::Stop,
+            "POP" => Op::Pop,
+            "DUP" => Op::Dup,
+            "SWAP" => Op::Swap,
+            "ADD" => Op::Add,
+            "SUB" => Op::Sub,
+            "MUL" => Op::Mul,
+            "DIV" => Op::Div,
+            "MOD" => Op::Mod,
+            "EQ" => Op::Eq,
+            "LT" => Op::Lt,
+            "GT" => Op::Gt,
+            "NOT" => Op::Not,
+            "LOAD" => Op::Load,
+            "STORE" => Op::Store,
+            "CALLER" => Op::Caller,
+            "VALUE" => Op::CallValue,
+            "ARG" => Op::Arg,
+            "HEIGHT" => Op::Height,
+            "BALANCE" => Op::Balance,
+            "TRANSFER" => Op::Transfer,
+            "REVERT" => Op::Revert,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Gas charged before the instruction runs
+    pub fn gas(&self) -> u64 {
+        match self {
+            Op::Stop | Op::Revert => 0,
+            Op::Push(_) | Op::PushStr(_) | Op::Pop | Op::Dup | Op::Swap => 1,
+            Op::Caller | Op::CallValue | Op::Arg | Op::Height | Op::Balance => 2,
+            Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod | Op::Eq | Op::Lt | Op::Gt | Op::Not => 3,
+            Op::Jump(_) | Op::JumpIf(_) => 5,
+            Op::Load => 20,
+            Op::Store => 100,
+            Op::Transfer => 200,
+        }
+    }
+}
+
+/// Encode instructions into bytecode
+pub fn encode(code: &[Op]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for op in code {
+        bytes.push(op.opcode());
+        match op {
+            Op::Push(int) => bytes.extend_from_slice(&int.to_be_bytes()),
+            Op::PushStr(string) => {
+                bytes.push(string.len() as u8);
+                bytes.extend_from_slice(string.as_bytes());
+            }
+            Op::Jump(target) | Op::JumpIf(target) => bytes.extend_from_slice(&target.to_be_bytes()),
+            _ => {}
+        }
+    }
+    bytes
+}
+
+/// Decode bytecode into instructions, checking that every jump lands inside the program
+pub fn decode(bytes: &[u8]) -> Result<Vec<Op>> {
+    if bytes.len() > MAX_CODE_SIZE {
+        return Err(anyhow::anyhow!("Code is {} bytes long, above the limit of {}", bytes.len(), MAX_CODE_SIZE));
+    }
+    let mut code = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() {
+        let opcode = bytes[position];
+        position += 1;
+        let mut operand = |length: usize| -> Result<&[u8]> {
+            let operand = bytes
+                .get(position..position + length)
+                .ok_or_else(|| anyhow::anyhow!("Truncated operand of opcode {:#04x}", opcode))?;
+            position += length;
+            Ok(operand)
+        };
+        let op = match opcode {
+            0x01 => Op::Push(u128::from_be_bytes(operand(16)?.try_into().unwrap())),
+            0x02 => {
+                let length = operand(1)?[0] as usize;
+                let string = std::str::from_utf8(operand(length)?)
+                    .map_err(|_| anyhow::anyhow!("String operand is not UTF-8"))?;
+                Op::PushStr(string.to_string())
+            }
+            0x30 => Op::Jump(u16::from_be_bytes(operand(2)?.try_into().unwrap())),
+            0x31 => Op::JumpIf(u16::from_be_bytes(operand(2)?.try_into().unwrap())),
+            _ => {
+                let op = [
+                    Op::Stop, Op::Pop, Op::Dup, Op::Swap, Op::Add, Op::Sub, Op::Mul, Op::Div, Op::Mod,
+                    Op::Eq, Op::Lt, Op::Gt, Op::Not, Op::Load, Op::Store, Op::Caller, Op::CallValue,
+                    Op::Arg, Op::Height, Op::Balance, Op::Transfer, Op::Revert,
+                ]
+                .into_iter()
+                .find(|op| op.opcode() == opcode);
+                op.ok_or_else(|| anyhow::anyhow!("Unknown opcode {:#04x}", opcode))?
+            }
+        };
+        code.push(op);
+    }
+    for op in &code {
+        if let Op::Jump(target) | Op::JumpIf(target) = op {
+            if *target as usize > code.len() {
+                return Err(anyhow::anyhow!("Jump to instruction {} past the end of the code", target));
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Assemble source code into bytecode
+///
+/// Each line holds one instruction, optionally preceded by a `label:`, and `;` starts a comment.
+/// `PUSH` takes an integer or a double quoted string, `JUMP` and `JUMPI` take a label.
+pub fn assemble(source: &str) -> Result<Vec<u8>> {
+    // First pass: strip comments and resolve labels to instruction indexes, leaving string literals alone
+    let mut labels = HashMap::new();
+    let mut lines = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let mut line = split_unquoted(line, ';').map_or(line, |(code, _)| code).trim();
+        if let Some((label, rest)) = split_unquoted(line, ':') {
+            let label = label.trim();
+            if label.is_empty() || label.contains(char::is_whitespace) || label.contains('"') {
+                return Err(anyhow::anyhow!("Line {}: Invalid label \"{}\"", number + 1, label));
+            } else if labels.insert(label.to_string(), lines.len()).is_some() {
+                return Err(anyhow::anyhow!("Line {}: Label {} is defined twice", number + 1, label));
+            }
+            line = rest.trim();
+        }
+        if !line.is_empty() {
+            lines.push((number + 1, line));
+        }
+    }
+
+    // Second pass: translate each instruction
+    let mut code = Vec::new();
+    for (number, line) in lines {
+        let (mnemonic, operand) = match line.split_once(char::is_whitespace) {
+            Some((mnemonic, operand)) => (mnemonic.to_uppercase(), operand.trim()),
+            None => (line.to_uppercase(), ""),
+        };
+        let op = match mnemonic.as_str() {
+            "PUSH" => {
+                if let Some(string) = operand.strip_prefix('"').and_then(|operand| operand.strip_suffix('"')) {
+                    if string.len() > u8::MAX as usize {
+                        return Err(anyhow::anyhow!("Line {}: String is longer than {} bytes", number, u8::MAX));
+                    }
+                    Op::PushStr(string.to_string())
+                } else {
+                    let int = operand
+                        .parse::<u128>()
+                        .map_err(|_| anyhow::anyhow!("Line {}: Invalid PUSH operand \"{}\"", number, operand))?;
+                    Op::Push(int)
+                }
+            }
+            "JUMP" | "JUMPI" => {
+                let target = *labels
+                    .get(operand)
+                    .ok_or_else(|| anyhow::anyhow!("Line {}: Unknown label \"{}\"", number, operand))?;
+                let target = u16::try_from(target).map_err(|_| anyhow::anyhow!("Line {}: Program is too long", number))?;
+                if mnemonic == "JUMP" { Op::Jump(target) } else { Op::JumpIf(target) }
+            }
+            _ => {
+                let op = Op::from_mnemonic(&mnemonic)
+                    .ok_or_else(|| anyhow::anyhow!("Line {}: Unknown instruction {}", number, mnemonic))?;
+                if !operand.is_empty() {
+                    return Err(anyhow::anyhow!("Line {}: {} takes no operand", number, mnemonic));
+                }
+                op
+            }
+        };
+        code.push(op);
+    }
+    let bytes = encode(&code);
+    if bytes.len() > MAX_CODE_SIZE {
+        return Err(anyhow::anyhow!("Code is {} bytes long, above the limit of {}", bytes.len(), MAX_CODE_SIZE));
+    }
+    Ok(bytes)
+}
+
+/// Split `line` around the first `separator` outside a string literal
+fn split_unquoted(line: &str, separator: char) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (index, char) in line.char_indices() {
+        if char == '"' {
+            quoted = !quoted;
+        } else if char == separator && !quoted {
+            return Some((&line[..index], &line[index + char.len_utf8()..]));
+        }
+    }
+    None
+}
+
+impl Execution {
+    pub fn get_storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    pub fn get_transfers(&self) -> &Vec<(String, u128)> {
+        &self.transfers
+    }
+
+    pub fn get_gas_used(&self) -> u64 {
+        self.gas_used
+    }
+}
+
+struct Machine {
+    stack: Vec<Value>,
+}
+
+impl Machine {
+    fn push(&mut self, value: Value) -> Result<()> {
+        if self.stack.len() >= MAX_STACK_DEPTH {
+            return Err(anyhow::anyhow!("Stack overflow"));
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Value> {
+        self.stack.pop().ok_or_else(|| anyhow::anyhow!("Stack underflow"))
+    }
+
+    fn pop_int(&mut self) -> Result<u128> {
+        self.pop()?.as_int()
+    }
+
+    fn pop_slot(&mut self) -> Result<u64> {
+        let slot = self.pop_int()?;
+        u64::try_from(slot).map_err(|_| anyhow::anyhow!("Storage slot {} is out of range", slot))
+    }
+
+    /// Apply an arithmetic or comparison operator to the two topmost integers, the deeper one on the left
+    fn binary(&mut self, operator: impl Fn(u128, u128) -> Option<u128>, name: &str) -> Result<()> {
+        let right = self.pop_int()?;
+        let left = self.pop_int()?;
+        let result = operator(left, right).ok_or_else(|| anyhow::anyhow!("{} of {} and {} failed", name, left, right))?;
+        self.push(Value::Int(result))
+    }
+}
+
+/// Run a contract call against its storage, without side effects
+///
+/// Fails when the code reverts, runs out of gas or misuses the stack; nothing of the call should be kept then.
+pub fn execute(code: &[Op], mut storage: Storage, env: &Env, gas_limit: u64) -> Result<Execution> {
+    let mut machine = Machine { stack: Vec::new() };
+    let mut transfers = Vec::new();
+    let mut balance = env.balance;
+    let mut gas_used: u64 = 0;
+    let mut pc = 0;
+    while let Some(op) = code.get(pc) {
+        gas_used += op.gas();
+        if gas_used > gas_limit {
+            return Err(anyhow::anyhow!("Out of gas at instruction {}, limit is {}", pc, gas_limit));
+        }
+        pc += 1;
+        match op {
+            Op::Stop => break,
+            Op::Push(int) => machine.push(Value::Int(*int))?,
+            Op::PushStr(string) => machine.push(Value::Str(string.clone()))?,
+            Op::Pop => {
+                machine.pop()?;
+            }
+            Op::Dup => {
+                let top = machine.stack.last().cloned().ok_or_else(|| anyhow::anyhow!("Stack underflow"))?;
+                machine.push(top)?;
+            }
+            Op::Swap => {
+                let top = machine.pop()?;
+                let below = machine.pop()?;
+                machine.push(top)?;
+                machine.push(below)?;
+            }
+            Op::Add => machine.binary(u128::checked_add, "Addition")?,
+            Op::Sub => machine.binary(u128::checked_sub, "Subtraction")?,
+            Op::Mul => machine.binary(u128::checked_mul, "Multiplication")?,
+            Op::Div => machine.binary(u128::checked_div, "Division")?,
+            Op::Mod => machine.binary(u128::checked_rem, "Modulo")?,
+            Op::Eq => {
+                let right = machine.pop()?;
+                let left = machine.pop()?;
+                machine.push(Value::Int((left == right) as u128))?;
+            }
+            Op::Lt => machine.binary(|left, right| Some((left < right) as u128), "Comparison")?,
+            Op::Gt => machine.binary(|left, right| Some((left > right) as u128), "Comparison")?,
+            Op::Not => {
+                let int = machine.pop_int()?;
+                machine.push(Value::Int((int == 0) as u128))?;
+            }
+            Op::Jump(target) => pc = *target as usize,
+            Op::JumpIf(target) => {
+                if machine.pop_int()? != 0 {
+                    pc = *target as usize;
+                }
+            }
+            Op::Load => {
+                let slot = machine.pop_slot()?;
+                machine.push(storage.get(&slot).cloned().unwrap_or(Value::Int(0)))?;
+            }
+            Op::Store => {
+                let slot = machine.pop_slot()?;
+                let value = machine.pop()?;
+                // Zero is what empty slots read as, so storing it frees the slot
+                if value == Value::Int(0) {
+                    storage.remove(&slot);
+                } else {
+                    if let Value::Str(string) = &value {
+                        if string.len() > MAX_VALUE_SIZE {
+                            return Err(anyhow::anyhow!("String of {} bytes is too long to store, slots hold at most {} bytes", string.len(), MAX_VALUE_SIZE));
+                        }
+                    }
+                    if !storage.contains_key(&slot) && storage.len() >= MAX_STORAGE_SLOTS {
+                        return Err(anyhow::anyhow!("Storage is full, contracts hold at most {} slots", MAX_STORAGE_SLOTS));
+                    }
+                    storage.insert(slot, value);
+                }
+            }
+            Op::Caller => machine.push(Value::Str(env.caller.clone()))?,
+            Op::CallValue => machine.push(Value::Int(env.value))?,
+            Op::Arg => {
+                let index = machine.pop_int()?;
+                let arg = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| env.args.get(index))
+                    .ok_or_else(|| anyhow::anyhow!("Argument {} is missing", index))?;
+                machine.push(arg.clone())?;
+            }
+            Op::Height => machine.push(Value::Int(env.height as u128))?,
+            Op::Balance => machine.push(Value::Int(balance))?,
+            Op::Transfer => {
+                let amount = machine.pop_int()?;
+                let receiver = machine.pop()?.as_str()?.clone();
+                if amount == 0 {
+                    return Err(anyhow::anyhow!("Transfer amount must be greater than 0"));
+                }
+                balance = balance.checked_sub(amount).ok_or_else(|| anyhow::anyhow!("Insufficient funds"))?;
+                transfers.push((receiver, amount));
+            }
+            Op::Revert => return Err(anyhow::anyhow!("Reverted at instruction {}", pc - 1)),
+        }
+    }
+    Ok(Execution {
+        storage,
+        transfers,
+        gas_used
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> Env {
+        Env { caller: "alice".to_string(), value: 0, args: Vec::new(), height: 1, balance: 0 }
+    }
+
+    fn run(source: &str, gas_limit: u64) -> Result<Execution> {
+        execute(&decode(&assemble(source)?)?, Storage::new(), &env(), gas_limit)
+    }
+
+    #[test]
+    fn assembly_round_trips_through_bytecode() {
+        let source = "
+            start: PUSH \"a; b: c\" ; strings keep their ; and :
+            push 1
+            JUMPI start
+            end: STOP
+        ";
+        let bytes = assemble(source).unwrap();
+        let code = decode(&bytes).unwrap();
+        assert_eq!(code, vec![Op::PushStr("a; b: c".to_string()), Op::Push(1), Op::JumpIf(0), Op::Stop]);
+        assert_eq!(encode(&code), bytes);
+    }
+
+    #[test]
+    fn calls_stop_when_they_run_out_of_gas() {
+        let source = "loop: JUMP loop";
+        assert!(run(source, 100).unwrap_err().to_string().contains("Out of gas"));
+
+        let execution = run("PUSH 7\nPUSH 1\nSTORE", 102).unwrap();
+        assert_eq!(execution.get_gas_used(), 102);
+        assert_eq!(execution.get_storage().get(&1), Some(&Value::Int(7)));
+        assert!(run("PUSH 7\nPUSH 1\nSTORE", 101).is_err());
+    }
+
+    #[test]
+    fn stack_underflow_fails_the_call() {
+        assert!(run("PUSH 1\nADD", 100).unwrap_err().to_string().contains("Stack underflow"));
+        assert!(run("DUP", 100).unwrap_err().to_string().contains("Stack underflow"));
+    }
+
+    #[test]
+    fn jumps_must_land_inside_the_code() {
+        assert!(assemble("JUMP nowhere").unwrap_err().to_string().contains("Unknown label"));
+        // Jumping right past the last instruction stops the call, any further is invalid
+        assert!(decode(&encode(&[Op::Jump(1)])).is_ok());
+        assert!(decode(&encode(&[Op::Jump(2)])).unwrap_err().to_string().contains("past the end"));
+        assert!(decode(&[0x30, 0x00]).unwrap_err().to_string().contains("Truncated operand"));
+    }
+}
@@ -1,5 +1,6 @@
 mod request;
 pub use request::*;
 
+#[allow(clippy::module_inception)]
 mod server;
 pub use server::*;
@@ -1,10 +1,24 @@
+use std::fs;
 use std::process;
-use reqwest;
+use crate::model::{assemble, AllowanceApproval, AllowanceTransfer, AssetIssue, BatchLeg, BatchTransfer, ContractCall, ContractDeploy, EscrowCancel, EscrowTransfer, FailurePolicy, MultisigApproval, MultisigCreate, MultisigProposal, ScheduleCancel, ScheduleCreate, Signed, Transaction, Transfer, Value, NATIVE_ASSET};
+use anyhow::Result;
+use ed25519_dalek::SigningKey;
 
 const B_CHAIN_URL: &str = "http://127.0.0.1:8000";
 
-pub fn balance(id: String) {
-    let url: String = format!("{}/balance/{}", B_CHAIN_URL, id); 
+pub fn balance(id: String, asset: Option<String>, height: Option<u64>) {
+    let mut params = Vec::new();
+    if let Some(asset) = asset {
+        params.push(format!("asset={}", asset));
+    }
+    if let Some(height) = height {
+        params.push(format!("height={}", height));
+    }
+    let url: String = if params.is_empty() {
+        format!("{}/balance/{}", B_CHAIN_URL, id)
+    } else {
+        format!("{}/balance/{}?{}", B_CHAIN_URL, id, params.join("&"))
+    };
     match reqwest::blocking::Client::new()
         .get(url)
         .send()
@@ -20,12 +34,15 @@ pub fn balance(id: String) {
     }
 }
 
-pub fn create_account(id: String, balance: u128) {
+pub fn create_account(id: String, balance: u128, public_key: String) {
     let url: String = format!("{}/account/", B_CHAIN_URL); 
+    let transaction = Transaction::CreateAccount { id: id.clone(), balance, public_key: public_key.clone() };
+    println!("Transaction id: {}", transaction.hash());
 
     let body = serde_json::json!({
         "id": id,
         "balance": balance,
+        "public_key": public_key,
     });
 
     match reqwest::blocking::Client::new()
@@ -44,12 +61,423 @@ pub fn create_account(id: String, balance: u128) {
     }
 }
 
-pub fn transfer(from_account: String, to_account: String, amount: u128) {
+/// Nonce the next signed transaction of the account must carry, as reported by the node
+fn fetch_next_nonce(id: &String) -> u64 {
+    let url: String = format!("{}/accounts/{}", B_CHAIN_URL, id);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) if response.status().is_success() => {
+            let body: serde_json::Value = response.json().unwrap_or_default();
+            body["next_nonce"].as_u64().expect("Invalid account response")
+        }
+        Ok(response) => {
+            eprintln!("Error fetching the nonce of {}: {}", id, response.text().unwrap());
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn account(id: String) {
+    let url: String = format!("{}/accounts/{}", B_CHAIN_URL, id);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer(from_account: String, to_account: String, amount: u128, asset: String, fee: u128, nonce: Option<u64>, secret_key: &SigningKey, dry_run: bool) {
     let url: String = format!("{}/transfer/", B_CHAIN_URL); 
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&from_account));
+    let transfer = Transfer::new(from_account.clone(), to_account.clone(), amount, nonce).with_asset(asset.clone()).with_fee(fee).sign(secret_key);
+    let signature = transfer.get_signature().clone();
+    let transaction = Transaction::Transfer(transfer);
+    println!("Transaction id: {}", transaction.hash());
+    if dry_run {
+        simulate(&transaction);
+        return;
+    }
+    let body = serde_json::json!({
+        "from": from_account,
+        "to": to_account,
+        "amount": amount,
+        "asset": asset,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+
+}
+
+/// Ask the node how a signed transaction would turn out, without submitting it
+pub fn simulate(transaction: &Transaction) {
+    let url: String = format!("{}/simulate", B_CHAIN_URL);
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(transaction)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Read the legs of a batch from a CSV file with `from,to,amount[,asset]` rows, all sent by the same account.
+/// Blank lines, `#` comments and a `from,to,...` header row are skipped.
+pub fn read_batch_file(path: &str) -> Result<(String, Vec<BatchLeg>)> {
+    let contents = fs::read_to_string(path)?;
+    let mut sender: Option<String> = None;
+    let mut legs = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("from,") {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 3 || fields.len() > 4 {
+            return Err(anyhow::anyhow!("Line {}: expected from,to,amount[,asset]", number + 1));
+        }
+        match &sender {
+            Some(sender) if sender != fields[0] => {
+                return Err(anyhow::anyhow!("Line {}: every leg must be sent by {}", number + 1, sender));
+            }
+            Some(_) => {}
+            None => sender = Some(fields[0].to_string()),
+        }
+        let amount = fields[2].parse::<u128>().map_err(|_| anyhow::anyhow!("Line {}: invalid amount {}", number + 1, fields[2]))?;
+        let asset = fields.get(3).copied().unwrap_or(NATIVE_ASSET);
+        legs.push(BatchLeg::new(fields[1].to_string(), amount, asset.to_string()));
+    }
+    match sender {
+        Some(sender) => Ok((sender, legs)),
+        None => Err(anyhow::anyhow!("{} has no transfers", path)),
+    }
+}
+
+pub fn transfer_batch(from_account: String, legs: Vec<BatchLeg>, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/batch", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&from_account));
+    let batch = BatchTransfer::new(from_account.clone(), legs.clone(), fee, nonce).sign(secret_key);
+    let signature = batch.get_signature().clone();
+    println!("Transaction id: {}", Transaction::Batch(batch).hash());
+    let legs: Vec<serde_json::Value> = legs
+        .iter()
+        .map(|leg| serde_json::json!({
+            "to": leg.get_receiver_id(),
+            "amount": leg.get_amount(),
+            "asset": leg.get_asset(),
+        }))
+        .collect();
+    let body = serde_json::json!({
+        "from": from_account,
+        "legs": legs,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn escrow(from_account: String, to_account: String, amount: u128, asset: String, release_height: u64, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/escrow/", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&from_account));
+    let escrow = EscrowTransfer::new(from_account.clone(), to_account.clone(), amount, asset.clone(), release_height, fee, nonce).sign(secret_key);
+    let signature = escrow.get_signature().clone();
+    println!("Transaction id: {}", Transaction::Escrow(escrow).hash());
+    let body = serde_json::json!({
+        "from": from_account,
+        "to": to_account,
+        "amount": amount,
+        "asset": asset,
+        "release_height": release_height,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn cancel_escrow(from_account: String, escrow: String, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/escrow/cancel", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&from_account));
+    let cancel = EscrowCancel::new(from_account.clone(), escrow.clone(), fee, nonce).sign(secret_key);
+    let signature = cancel.get_signature().clone();
+    println!("Transaction id: {}", Transaction::CancelEscrow(cancel).hash());
+    let body = serde_json::json!({
+        "from": from_account,
+        "escrow": escrow,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn escrows(account: Option<String>) {
+    let url: String = match account {
+        Some(account) => format!("{}/escrows?account={}", B_CHAIN_URL, account),
+        None => format!("{}/escrows", B_CHAIN_URL),
+    };
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn schedule(from_account: String, to_account: String, amount: u128, asset: String, interval: u64, count: u64, on_failure: FailurePolicy, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/schedule/", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&from_account));
+    let schedule = ScheduleCreate::new(from_account.clone(), to_account.clone(), amount, asset.clone(), interval, count, on_failure, fee, nonce).sign(secret_key);
+    let signature = schedule.get_signature().clone();
+    println!("Transaction id: {}", Transaction::Schedule(schedule).hash());
+    let body = serde_json::json!({
+        "from": from_account,
+        "to": to_account,
+        "amount": amount,
+        "asset": asset,
+        "interval": interval,
+        "count": count,
+        "on_failure": on_failure.as_str(),
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn cancel_schedule(from_account: String, schedule: String, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/schedule/cancel", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&from_account));
+    let cancel = ScheduleCancel::new(from_account.clone(), schedule.clone(), fee, nonce).sign(secret_key);
+    let signature = cancel.get_signature().clone();
+    println!("Transaction id: {}", Transaction::CancelSchedule(cancel).hash());
     let body = serde_json::json!({
         "from": from_account,
+        "schedule": schedule,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn schedules(account: Option<String>) {
+    let url: String = match account {
+        Some(account) => format!("{}/schedules?account={}", B_CHAIN_URL, account),
+        None => format!("{}/schedules", B_CHAIN_URL),
+    };
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn create_multisig(creator: String, id: String, members: Vec<String>, threshold: usize, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/multisig/", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&creator));
+    let create = MultisigCreate::new(creator.clone(), id.clone(), members.clone(), threshold, fee, nonce).sign(secret_key);
+    let signature = create.get_signature().clone();
+    println!("Transaction id: {}", Transaction::CreateMultisig(create).hash());
+    let body = serde_json::json!({
+        "creator": creator,
+        "id": id,
+        "members": members,
+        "threshold": threshold,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn propose(proposer: String, account: String, to_account: String, amount: u128, asset: String, expires_in: u64, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/multisig/propose", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&proposer));
+    let proposal = MultisigProposal::new(proposer.clone(), account.clone(), to_account.clone(), amount, asset.clone(), expires_in, fee, nonce).sign(secret_key);
+    let signature = proposal.get_signature().clone();
+    println!("Transaction id: {}", Transaction::Propose(proposal).hash());
+    let body = serde_json::json!({
+        "proposer": proposer,
+        "account": account,
         "to": to_account,
         "amount": amount,
+        "asset": asset,
+        "expires_in": expires_in,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn approve(member: String, account: String, proposal: String, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/multisig/approve", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&member));
+    let approval = MultisigApproval::new(member.clone(), account.clone(), proposal.clone(), fee, nonce).sign(secret_key);
+    let signature = approval.get_signature().clone();
+    println!("Transaction id: {}", Transaction::Approve(approval).hash());
+    let body = serde_json::json!({
+        "member": member,
+        "account": account,
+        "proposal": proposal,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
     });
 
     match reqwest::blocking::Client::new()
@@ -66,5 +494,362 @@ pub fn transfer(from_account: String, to_account: String, amount: u128) {
             process::exit(1);
         }
     }
+}
 
+pub fn proposals(account: Option<String>) {
+    let url: String = match account {
+        Some(account) => format!("{}/proposals?account={}", B_CHAIN_URL, account),
+        None => format!("{}/proposals", B_CHAIN_URL),
+    };
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn allow(owner: String, spender: String, amount: u128, asset: String, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/allowance/", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&owner));
+    let approval = AllowanceApproval::new(owner.clone(), spender.clone(), amount, asset.clone(), fee, nonce).sign(secret_key);
+    let signature = approval.get_signature().clone();
+    println!("Transaction id: {}", Transaction::ApproveAllowance(approval).hash());
+    let body = serde_json::json!({
+        "owner": owner,
+        "spender": spender,
+        "amount": amount,
+        "asset": asset,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_from(spender: String, owner: String, to_account: String, amount: u128, asset: String, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/transfer-from", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&spender));
+    let transfer = AllowanceTransfer::new(spender.clone(), owner.clone(), to_account.clone(), amount, asset.clone(), fee, nonce).sign(secret_key);
+    let signature = transfer.get_signature().clone();
+    println!("Transaction id: {}", Transaction::TransferFrom(transfer).hash());
+    let body = serde_json::json!({
+        "spender": spender,
+        "owner": owner,
+        "to": to_account,
+        "amount": amount,
+        "asset": asset,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn allowance(owner: String, spender: String, asset: String) {
+    let url: String = format!("{}/allowance/{}/{}?asset={}", B_CHAIN_URL, owner, spender, asset);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Assemble the contract source in a file, returning its hex encoded bytecode
+pub fn read_contract_file(path: &str) -> Result<String> {
+    let source = fs::read_to_string(path)?;
+    Ok(hex::encode(assemble(&source)?))
+}
+
+pub fn deploy_contract(deployer: String, contract: String, code: String, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/contract/deploy", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&deployer));
+    let deploy = ContractDeploy::new(deployer.clone(), contract.clone(), code.clone(), fee, nonce).sign(secret_key);
+    let signature = deploy.get_signature().clone();
+    println!("Transaction id: {}", Transaction::Deploy(deploy).hash());
+    let body = serde_json::json!({
+        "deployer": deployer,
+        "contract": contract,
+        "code": code,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn call_contract(caller: String, contract: String, value: u128, args: Vec<Value>, gas_limit: u64, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/contract/call", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&caller));
+    let call = ContractCall::new(caller.clone(), contract.clone(), value, args.clone(), gas_limit, fee, nonce).sign(secret_key);
+    let signature = call.get_signature().clone();
+    println!("Transaction id: {}", Transaction::Call(call).hash());
+    let body = serde_json::json!({
+        "caller": caller,
+        "contract": contract,
+        "value": value,
+        "args": args,
+        "gas_limit": gas_limit,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn contract(id: String) {
+    let url: String = format!("{}/contract/{}", B_CHAIN_URL, id);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn issue_asset(issuer: String, asset: String, supply: u128, fee: u128, nonce: Option<u64>, secret_key: &SigningKey) {
+    let url: String = format!("{}/asset/", B_CHAIN_URL);
+    let nonce = nonce.unwrap_or_else(|| fetch_next_nonce(&issuer));
+    let issue = AssetIssue::new(issuer.clone(), asset.clone(), supply, fee, nonce).sign(secret_key);
+    let signature = issue.get_signature().clone();
+    println!("Transaction id: {}", Transaction::IssueAsset(issue).hash());
+    let body = serde_json::json!({
+        "issuer": issuer,
+        "asset": asset,
+        "supply": supply,
+        "fee": fee,
+        "nonce": nonce,
+        "signature": signature,
+    });
+
+    match reqwest::blocking::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn assets() {
+    let url: String = format!("{}/assets", B_CHAIN_URL);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn supply() {
+    let url: String = format!("{}/supply", B_CHAIN_URL);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn verify() {
+    let url: String = format!("{}/verify", B_CHAIN_URL);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn mempool() {
+    let url: String = format!("{}/mempool", B_CHAIN_URL);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn tx_status(id: String) {
+    let url: String = format!("{}/tx/{}", B_CHAIN_URL, id);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn history(id: String, offset: usize, limit: usize) {
+    let url: String = format!("{}/accounts/{}/history?offset={}&limit={}", B_CHAIN_URL, id, offset, limit);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn block(height: String) {
+    let url: String = format!("{}/blocks/{}", B_CHAIN_URL, height);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn blocks(from: u64, limit: usize) {
+    let url: String = format!("{}/blocks?from={}&limit={}", B_CHAIN_URL, from, limit);
+    match reqwest::blocking::Client::new()
+        .get(url)
+        .send()
+    {
+        Ok(response) => {
+            println!("Status: {}", response.status());
+            println!("Body: {}", response.text().unwrap());
+        }
+        Err(err) => {
+            eprintln!("Error making request: {}", err);
+            process::exit(1);
+        }
+    }
 }
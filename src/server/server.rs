@@ -1,15 +1,111 @@
 use mio::{Events, Poll, Token, Ready, PollOpt};
 use std::io::{Read, Write};
 use std::net::SocketAddr;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use crate::model::Blockchain;
+use crate::model::{AllowanceApproval, AllowanceTransfer, AssetIssue, BatchLeg, BatchTransfer, Blockchain, Clock, ContractCall, ContractDeploy, EscrowCancel, EscrowTransfer, FailurePolicy, MultisigApproval, MultisigCreate, MultisigProposal, ScheduleCancel, ScheduleCreate, Config, Signed, Transaction, Transfer, Unavailable, Value, NATIVE_ASSET};
 use anyhow::Result;
 
 
-pub fn server_single_thread() {
+/// Maximum number of transactions returned by a single history request
+const HISTORY_PAGE_SIZE: usize = 100;
+/// Maximum number of blocks returned by a single block listing
+const BLOCKS_PAGE_SIZE: usize = 100;
+/// Largest request the node reads, headers included
+const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+/// Longest the node waits for the rest of a request before answering 400
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+
+/// Split the request target following `prefix` into the path and its query parameters
+fn parse_target(request_str: &str, prefix: &str) -> (String, HashMap<String, String>) {
+    let target_end = request_str.find(" HTTP/1.1").unwrap_or(request_str.len());
+    let target = &request_str[prefix.len()..target_end];
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params = query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    (path.to_string(), params)
+}
+
+
+/// Size of the request once complete: its headers plus the body announced by `Content-Length`, if the headers are all there
+fn request_length(request: &[u8]) -> Option<usize> {
+    let headers_end = request.windows(4).position(|window| window == b"\r\n\r\n")? + 4;
+    let headers = String::from_utf8_lossy(&request[..headers_end]);
+    let content_length = headers
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    Some(headers_end + content_length)
+}
+
+/// Read a whole request, waiting for the rest of the body when it spans several reads.
+/// The node serves one client at a time, so a request that is not complete within `REQUEST_READ_TIMEOUT` is dropped.
+fn read_request(socket: &mut mio::net::TcpStream) -> std::io::Result<Vec<u8>> {
+    let deadline = Instant::now() + REQUEST_READ_TIMEOUT;
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    loop {
+        match socket.read(&mut buffer) {
+            Ok(0) => return Ok(request),
+            Ok(read) => {
+                request.extend_from_slice(&buffer[..read]);
+                let length = request_length(&request);
+                if length.is_some_and(|length| request.len() >= length) {
+                    return Ok(request);
+                } else if request.len() > MAX_REQUEST_SIZE || length.is_some_and(|length| length > MAX_REQUEST_SIZE) {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Request too large"));
+                }
+            }
+            Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "Request incomplete, timed out waiting for the rest of it"));
+                }
+                // Data not yet available, wait a bit
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Answer a submission with the ID of the queued transaction, whose receipt `GET /tx/<id>` serves once it is executed
+fn respond_with_id(result: Result<String>, socket: &mut mio::net::TcpStream) {
+    match result {
+        Ok(id) => {
+            let body = serde_json::json!({ "id": id, "status": "pending" });
+            let response = format!("HTTP/1.1 202 ACCEPTED\r\n\r\n{}", body);
+            let _ = socket.write_all(response.as_bytes());
+        }
+        // Only a full mempool or a halted chain is worth retrying, anything else is wrong with the transaction
+        Err(err) if err.downcast_ref::<Unavailable>().is_some() => {
+            let response = format!("HTTP/1.1 503 SERVICE UNAVAILABLE\r\n\r\n{}", err);
+            let _ = socket.write_all(response.as_bytes());
+        }
+        Err(err) => {
+            let response = format!("HTTP/1.1 400 BAD REQUEST\r\n\r\n{}", err);
+            let _ = socket.write_all(response.as_bytes());
+        }
+    }
+}
+
+
+pub fn server_single_thread(config: Config, clock: Arc<dyn Clock>) {
 
     // Create the blockchain
-    let mut blockchain = Blockchain::new();
+    let mut blockchain = match Blockchain::new(config, clock.clone()) {
+        Ok(blockchain) => blockchain,
+        Err(err) => {
+            eprintln!("Error loading the blockchain: {}", err);
+            return;
+        }
+    };
     let address: SocketAddr = "127.0.0.1:8000".parse().unwrap();
     let listener = match mio::net::TcpListener::bind(&address) {
         Ok(listener) => {
@@ -31,55 +127,74 @@ pub fn server_single_thread() {
         
 
     let mut events = Events::with_capacity(1024);
-    let mut last_process_time = Instant::now();
 
     println!("Server listening on 127.0.0.1:8080");
     loop {
 
-        poll.poll(&mut events, Some(Duration::from_millis(500))).unwrap();
+        // Wake up in time for the next block even when no request comes in
+        poll.poll(&mut events, Some(blockchain.time_to_next_block())).unwrap();
         for event in &events {
             // handle the event
             println!("Event: {:?}", event);
+            #[allow(clippy::single_match)]
             match event.token() {
                 Token(0) => {
                     println!("event readines: {:?}", event.readiness().is_readable());
                     if event.readiness().is_readable() {
                         // Accept incoming connections
                         while let Ok((mut socket, _)) = listener.accept() {
-                            let mut buffer = [0; 1024];
-                            let mut _request: Option<usize> = None;
-                            loop {
-                                match socket.read(&mut buffer) {
-                                    Ok(request) => {
-                                        // Data successfully read, continue processing
-                                        _request = Some(request);
-                                        break;
-                                    }
-                                    Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {
-                                        // Data not yet available, wait a bit
-                                        std::thread::sleep(Duration::from_millis(10));
-                                    }
-                                    Err(err) => {
-                                        eprintln!("Error reading from socket: {}", err);
-                                        let response = format!("HTTP/1.1 400 BAD REQUEST\r\n\r\n{}", err);
-                                        let _ = socket.write_all(response.as_bytes());
-                                        return;
-                                    }
+                            let request = match read_request(&mut socket) {
+                                Ok(request) => request,
+                                Err(err) => {
+                                    eprintln!("Error reading from socket: {}", err);
+                                    let response = format!("HTTP/1.1 400 BAD REQUEST\r\n\r\n{}", err);
+                                    let _ = socket.write_all(response.as_bytes());
+                                    continue;
                                 }
-                            }
-                            let request = _request.unwrap();
-                            let request_str = String::from_utf8_lossy(&buffer[..request]);
+                            };
+                            let request_str = String::from_utf8_lossy(&request);
 
                             let balance_prefix = "GET /balance/";
                             let create_account_prefix = "POST /account/";
                             let transfer_prefix = "POST /transfer/";
+                            let verify_prefix = "GET /verify";
+                            let mempool_prefix = "GET /mempool";
+                            let tx_prefix = "GET /tx/";
+                            let accounts_prefix = "GET /accounts/";
+                            let blocks_prefix = "GET /blocks";
+                            let issue_asset_prefix = "POST /asset/";
+                            let assets_prefix = "GET /assets";
+                            let supply_prefix = "GET /supply";
+                            let batch_prefix = "POST /batch";
+                            let cancel_escrow_prefix = "POST /escrow/cancel";
+                            let escrow_prefix = "POST /escrow/";
+                            let escrows_prefix = "GET /escrows";
+                            let cancel_schedule_prefix = "POST /schedule/cancel";
+                            let schedule_prefix = "POST /schedule/";
+                            let schedules_prefix = "GET /schedules";
+                            let propose_prefix = "POST /multisig/propose";
+                            let approve_prefix = "POST /multisig/approve";
+                            let create_multisig_prefix = "POST /multisig/";
+                            let proposals_prefix = "GET /proposals";
+                            let approve_allowance_prefix = "POST /allowance/";
+                            let allowance_prefix = "GET /allowance/";
+                            let transfer_from_prefix = "POST /transfer-from";
+                            let deploy_prefix = "POST /contract/deploy";
+                            let call_prefix = "POST /contract/call";
+                            let contract_prefix = "GET /contract/";
+                            let simulate_prefix = "POST /simulate";
 
                             if request_str.starts_with(balance_prefix) {
                                 // Balance ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
-                                let id_start: usize = balance_prefix.len();
-                                let id_end = request_str.find(" HTTP/1.1").unwrap_or(request_str.len());
-                                let id = request_str[id_start..id_end].to_string();
-                                let balance = blockchain.get_balance(&id.to_string()); 
+                                let (id, params) = parse_target(&request_str, balance_prefix);
+                                let asset = params.get("asset").map(String::as_str).unwrap_or(NATIVE_ASSET);
+                                let balance = match params.get("height") {
+                                    Some(height) => match height.parse::<u64>() {
+                                        Ok(height) => blockchain.get_balance_at(&id, asset, height),
+                                        Err(_) => Err(anyhow::anyhow!("Invalid height {}", height)),
+                                    },
+                                    None => blockchain.get_balance(&id, asset),
+                                };
                                 match balance {
                                     Ok(balance) => {
                                         let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", balance);
@@ -90,7 +205,7 @@ pub fn server_single_thread() {
                                         let _ = socket.write_all(response.as_bytes());
                                     }
                                 }
-                                
+                            
                             } else if request_str.starts_with(create_account_prefix) {
                                 // Transaction: Create account ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
                                 // Process request
@@ -99,25 +214,10 @@ pub fn server_single_thread() {
                                 let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
                                 let id = json_body["id"].as_str().unwrap_or_default().to_string();
                                 let balance = json_body["balance"].as_u64().unwrap_or_default() as u128;
-                                // Build callback
-                                fn callback(result: Result<()>, mut socket: mio::net::TcpStream) {
-                                    match result {
-                                        Ok(_) => {
-                                            let response: String = format!("HTTP/1.1 200 OK\r\n\r\nAccount created");
-                                            let _ = socket.write_all(response.as_bytes());
-                                        }
-                                        Err(err) => {
-                                            let response = format!("HTTP/1.1 400 BAD REQUEST\r\n\r\n{}", err);
-                                            let _ = socket.write_all(response.as_bytes());
-                                        }
-                                    }
-                                }
-                                // Convert the callback function to a closure
-                                let closure: Box<dyn FnOnce(Result<(), anyhow::Error>) + Send + 'static> = Box::new(move |result| {            
-                                    callback(result, socket);
-                                });
-                                // Add callback to queue
-                                blockchain.create_account(id, balance, closure)
+                                let public_key = json_body["public_key"].as_str().unwrap_or_default().to_string();
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.create_account(id, balance, public_key);
+                                respond_with_id(result, &mut socket);
 
 
                             } else if request_str.starts_with(transfer_prefix) {
@@ -130,29 +230,488 @@ pub fn server_single_thread() {
                                 let from = json_body["from"].as_str().unwrap_or_default().to_string();
                                 let to = json_body["to"].as_str().unwrap_or_default().to_string();
                                 let amount = json_body["amount"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let asset = json_body["asset"].as_str().unwrap_or(NATIVE_ASSET).to_string();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let transfer = Transfer::new(from, to, amount, nonce).with_asset(asset).with_fee(fee).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.add_transfer(transfer);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(batch_prefix) {
+                                // Transaction: batch ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let from = json_body["from"].as_str().unwrap_or_default().to_string();
+                                let legs: Vec<BatchLeg> = json_body["legs"].as_array().map(Vec::as_slice).unwrap_or_default()
+                                    .iter()
+                                    .map(|leg| BatchLeg::new(
+                                        leg["to"].as_str().unwrap_or_default().to_string(),
+                                        leg["amount"].as_u64().unwrap_or_default() as u128,
+                                        leg["asset"].as_str().unwrap_or(NATIVE_ASSET).to_string(),
+                                    ))
+                                    .collect();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let batch = BatchTransfer::new(from, legs, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.add_batch(batch);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(cancel_escrow_prefix) {
+                                // Transaction: cancel escrow ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let from = json_body["from"].as_str().unwrap_or_default().to_string();
+                                let escrow = json_body["escrow"].as_str().unwrap_or_default().to_string();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let cancel = EscrowCancel::new(from, escrow, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.cancel_escrow(cancel);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(escrow_prefix) {
+                                // Transaction: escrow ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let from = json_body["from"].as_str().unwrap_or_default().to_string();
+                                let to = json_body["to"].as_str().unwrap_or_default().to_string();
+                                let amount = json_body["amount"].as_u64().unwrap_or_default() as u128;
+                                let asset = json_body["asset"].as_str().unwrap_or(NATIVE_ASSET).to_string();
+                                let release_height = json_body["release_height"].as_u64().unwrap_or_default();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let escrow = EscrowTransfer::new(from, to, amount, asset, release_height, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.add_escrow(escrow);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(escrows_prefix) {
+                                // Escrows ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (_, params) = parse_target(&request_str, escrows_prefix);
+                                let escrows = blockchain.get_escrows(params.get("account"));
+                                let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::to_string(&escrows).unwrap());
+                                let _ = socket.write_all(response.as_bytes());
+
+                            } else if request_str.starts_with(cancel_schedule_prefix) {
+                                // Transaction: cancel schedule ~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let from = json_body["from"].as_str().unwrap_or_default().to_string();
+                                let schedule = json_body["schedule"].as_str().unwrap_or_default().to_string();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let cancel = ScheduleCancel::new(from, schedule, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.cancel_schedule(cancel);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(schedule_prefix) {
+                                // Transaction: schedule ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let from = json_body["from"].as_str().unwrap_or_default().to_string();
+                                let to = json_body["to"].as_str().unwrap_or_default().to_string();
+                                let amount = json_body["amount"].as_u64().unwrap_or_default() as u128;
+                                let asset = json_body["asset"].as_str().unwrap_or(NATIVE_ASSET).to_string();
+                                let interval = json_body["interval"].as_u64().unwrap_or_default();
+                                let count = json_body["count"].as_u64().unwrap_or_default();
+                                let on_failure = FailurePolicy::parse(json_body["on_failure"].as_str().unwrap_or("skip"));
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                match on_failure {
+                                    Some(on_failure) => {
+                                        let schedule = ScheduleCreate::new(from, to, amount, asset, interval, count, on_failure, fee, nonce).with_signature(signature);
+                                        // Answer as soon as the transaction is queued
+                                        let result = blockchain.add_schedule(schedule);
+                                        respond_with_id(result, &mut socket);
+                                    }
+                                    None => {
+                                        let response = "HTTP/1.1 400 BAD REQUEST\r\n\r\non_failure must be skip or cancel";
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                }
+
+                            } else if request_str.starts_with(schedules_prefix) {
+                                // Schedules ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (_, params) = parse_target(&request_str, schedules_prefix);
+                                let schedules = blockchain.get_schedules(params.get("account"));
+                                let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::to_string(&schedules).unwrap());
+                                let _ = socket.write_all(response.as_bytes());
+
+                            } else if request_str.starts_with(propose_prefix) {
+                                // Transaction: multisig proposal ~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let proposer = json_body["proposer"].as_str().unwrap_or_default().to_string();
+                                let account = json_body["account"].as_str().unwrap_or_default().to_string();
+                                let to = json_body["to"].as_str().unwrap_or_default().to_string();
+                                let amount = json_body["amount"].as_u64().unwrap_or_default() as u128;
+                                let asset = json_body["asset"].as_str().unwrap_or(NATIVE_ASSET).to_string();
+                                let expires_in = json_body["expires_in"].as_u64().unwrap_or_default();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let proposal = MultisigProposal::new(proposer, account, to, amount, asset, expires_in, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.propose(proposal);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(approve_prefix) {
+                                // Transaction: multisig approval ~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let member = json_body["member"].as_str().unwrap_or_default().to_string();
+                                let account = json_body["account"].as_str().unwrap_or_default().to_string();
+                                let proposal = json_body["proposal"].as_str().unwrap_or_default().to_string();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let approval = MultisigApproval::new(member, account, proposal, fee, nonce).with_signature(signature);
 
-                                // Build callback
-                                fn callback(result: Result<()>, mut socket: mio::net::TcpStream) {
-                                    match result {
-                                        Ok(_) => {
-                                            let response = format!("HTTP/1.1 200 OK\r\n\r\nTransfer complete");
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.approve(approval);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(create_multisig_prefix) {
+                                // Transaction: create multisig account ~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let creator = json_body["creator"].as_str().unwrap_or_default().to_string();
+                                let id = json_body["id"].as_str().unwrap_or_default().to_string();
+                                let members: Vec<String> = json_body["members"].as_array().map(Vec::as_slice).unwrap_or_default()
+                                    .iter()
+                                    .map(|member| member.as_str().unwrap_or_default().to_string())
+                                    .collect();
+                                let threshold = json_body["threshold"].as_u64().unwrap_or_default() as usize;
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let create = MultisigCreate::new(creator, id, members, threshold, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.create_multisig(create);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(proposals_prefix) {
+                                // Proposals ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (_, params) = parse_target(&request_str, proposals_prefix);
+                                let proposals = blockchain.get_proposals(params.get("account"));
+                                let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::to_string(&proposals).unwrap());
+                                let _ = socket.write_all(response.as_bytes());
+
+                            } else if request_str.starts_with(approve_allowance_prefix) {
+                                // Transaction: approve spender ~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let owner = json_body["owner"].as_str().unwrap_or_default().to_string();
+                                let spender = json_body["spender"].as_str().unwrap_or_default().to_string();
+                                let amount = json_body["amount"].as_u64().unwrap_or_default() as u128;
+                                let asset = json_body["asset"].as_str().unwrap_or(NATIVE_ASSET).to_string();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let approval = AllowanceApproval::new(owner, spender, amount, asset, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.approve_allowance(approval);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(allowance_prefix) {
+                                // Allowance ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (path, params) = parse_target(&request_str, allowance_prefix);
+                                let asset = params.get("asset").map(String::as_str).unwrap_or(NATIVE_ASSET);
+                                let allowance = match path.split_once('/') {
+                                    Some((owner, spender)) => blockchain.get_allowance(&owner.to_string(), &spender.to_string(), asset),
+                                    None => Err(anyhow::anyhow!("Expected /allowance/{{owner}}/{{spender}}")),
+                                };
+                                match allowance {
+                                    Ok(allowance) => {
+                                        let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", allowance);
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                    Err(err) => {
+                                        let response = format!("HTTP/1.1 404 NOT FOUND\r\n\r\n{}", err);
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                }
+
+                            } else if request_str.starts_with(transfer_from_prefix) {
+                                // Transaction: transfer from ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let spender = json_body["spender"].as_str().unwrap_or_default().to_string();
+                                let owner = json_body["owner"].as_str().unwrap_or_default().to_string();
+                                let to = json_body["to"].as_str().unwrap_or_default().to_string();
+                                let amount = json_body["amount"].as_u64().unwrap_or_default() as u128;
+                                let asset = json_body["asset"].as_str().unwrap_or(NATIVE_ASSET).to_string();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let transfer = AllowanceTransfer::new(spender, owner, to, amount, asset, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.transfer_from(transfer);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(deploy_prefix) {
+                                // Transaction: deploy contract ~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let deployer = json_body["deployer"].as_str().unwrap_or_default().to_string();
+                                let contract = json_body["contract"].as_str().unwrap_or_default().to_string();
+                                let code = json_body["code"].as_str().unwrap_or_default().to_string();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let deploy = ContractDeploy::new(deployer, contract, code, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.deploy_contract(deploy);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(call_prefix) {
+                                // Transaction: call contract ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let caller = json_body["caller"].as_str().unwrap_or_default().to_string();
+                                let contract = json_body["contract"].as_str().unwrap_or_default().to_string();
+                                let value = json_body["value"].as_u64().unwrap_or_default() as u128;
+                                let args: Vec<Value> = serde_json::from_value(json_body["args"].clone()).unwrap_or_default();
+                                let gas_limit = json_body["gas_limit"].as_u64().unwrap_or_default();
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let call = ContractCall::new(caller, contract, value, args, gas_limit, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.call_contract(call);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(contract_prefix) {
+                                // Contract ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (id, _) = parse_target(&request_str, contract_prefix);
+                                match blockchain.get_contract(&id) {
+                                    Ok(contract) => {
+                                        let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::to_string(contract).unwrap());
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                    Err(err) => {
+                                        let response = format!("HTTP/1.1 404 NOT FOUND\r\n\r\n{}", err);
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                }
+
+                            } else if request_str.starts_with(issue_asset_prefix) {
+                                // Transaction: issue asset ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let json_body: serde_json::Value = serde_json::from_str(body_str).unwrap_or_default();
+
+                                let issuer = json_body["issuer"].as_str().unwrap_or_default().to_string();
+                                let asset = json_body["asset"].as_str().unwrap_or_default().to_string();
+                                let supply = json_body["supply"].as_u64().unwrap_or_default() as u128;
+                                let fee = json_body["fee"].as_u64().unwrap_or_default() as u128;
+                                let nonce = json_body["nonce"].as_u64().unwrap_or_default();
+                                let signature = json_body["signature"].as_str().unwrap_or_default().to_string();
+                                let issue = AssetIssue::new(issuer, asset, supply, fee, nonce).with_signature(signature);
+
+                                // Answer as soon as the transaction is queued
+                                let result = blockchain.issue_asset(issue);
+                                respond_with_id(result, &mut socket);
+
+                            } else if request_str.starts_with(assets_prefix) {
+                                // Assets ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::to_string(&blockchain.get_assets()).unwrap());
+                                let _ = socket.write_all(response.as_bytes());
+
+                            } else if request_str.starts_with(simulate_prefix) {
+                                // Simulate transaction ~~~~~~~~~~~~~~~~~~~~~~~
+                                let body_start = request_str.find("\r\n\r\n").unwrap_or(request_str.len()) + "\r\n\r\n".len();
+                                let body_str = &request_str[body_start..];
+                                let simulation = serde_json::from_str::<Transaction>(body_str)
+                                    .map_err(|err| anyhow::anyhow!("Invalid transaction: {}", err))
+                                    .and_then(|transaction| blockchain.simulate(&transaction));
+                                match simulation {
+                                    Ok(simulation) => {
+                                        let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::to_string(&simulation).unwrap());
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                    Err(err) => {
+                                        let response = format!("HTTP/1.1 400 BAD REQUEST\r\n\r\n{}", err);
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                }
+
+                            } else if request_str.starts_with(supply_prefix) {
+                                // Supply ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let body = serde_json::json!({
+                                    "supply": blockchain.get_supply(),
+                                    "halted": blockchain.get_halted(),
+                                });
+                                let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
+                                let _ = socket.write_all(response.as_bytes());
+
+                            } else if request_str.starts_with(verify_prefix) {
+                                // Verify ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                match blockchain.verify() {
+                                    Ok(_) => {
+                                        let response = format!("HTTP/1.1 200 OK\r\n\r\nChain valid ({} blocks)", blockchain.get_height());
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                    Err(err) => {
+                                        let response = format!("HTTP/1.1 409 CONFLICT\r\n\r\n{}", err);
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                }
+
+                            } else if request_str.starts_with(mempool_prefix) {
+                                // Mempool ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let pending: Vec<serde_json::Value> = blockchain.get_mempool().iter()
+                                    .map(|pending| serde_json::json!({
+                                        "sequence": pending.sequence,
+                                        "transaction": pending.transaction,
+                                    }))
+                                    .collect();
+                                let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::Value::from(pending));
+                                let _ = socket.write_all(response.as_bytes());
+
+                            } else if request_str.starts_with(tx_prefix) {
+                                // Transaction status ~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (id, _) = parse_target(&request_str, tx_prefix);
+                                if let Some(receipt) = blockchain.get_receipt(&id) {
+                                    let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", serde_json::to_string(receipt).unwrap());
+                                    let _ = socket.write_all(response.as_bytes());
+                                } else if let Some(position) = blockchain.get_mempool().position(&id) {
+                                    let body = serde_json::json!({ "id": id, "status": "pending", "position": position });
+                                    let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
+                                    let _ = socket.write_all(response.as_bytes());
+                                } else if let Some(reason) = blockchain.get_dropped(&id) {
+                                    let body = serde_json::json!({ "id": id, "status": "dropped", "reason": reason });
+                                    let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
+                                    let _ = socket.write_all(response.as_bytes());
+                                } else {
+                                    let response = format!("HTTP/1.1 404 NOT FOUND\r\n\r\nTransaction {} not found", id);
+                                    let _ = socket.write_all(response.as_bytes());
+                                }
+
+                            } else if request_str.starts_with(accounts_prefix) {
+                                // Account ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (path, params) = parse_target(&request_str, accounts_prefix);
+                                if !path.contains('/') {
+                                    match blockchain.get_account(&path) {
+                                        Ok(account) => {
+                                            let body = serde_json::json!({
+                                                "account": account,
+                                                "next_nonce": blockchain.get_next_nonce(&path).unwrap(),
+                                            });
+                                            let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
                                             let _ = socket.write_all(response.as_bytes());
                                         }
                                         Err(err) => {
-                                            let response = format!("HTTP/1.1 400 BAD REQUEST\r\n\r\n{}", err);
+                                            let response = format!("HTTP/1.1 404 NOT FOUND\r\n\r\n{}", err);
+                                            let _ = socket.write_all(response.as_bytes());
+                                        }
+                                    }
+                                } else {
+                                    // Account history
+                                    let offset = params.get("offset").and_then(|offset| offset.parse::<usize>().ok()).unwrap_or(0);
+                                    let limit = params.get("limit").and_then(|limit| limit.parse::<usize>().ok()).unwrap_or(HISTORY_PAGE_SIZE).min(HISTORY_PAGE_SIZE);
+                                    match path.strip_suffix("/history").map(|id| blockchain.get_history(&id.to_string(), offset, limit)) {
+                                        Some(Ok((total, page))) => {
+                                            let transactions: Vec<serde_json::Value> = page
+                                                .into_iter()
+                                                .map(|(transaction, receipt)| serde_json::json!({
+                                                    "transaction": transaction,
+                                                    "receipt": receipt,
+                                                }))
+                                                .collect();
+                                            let body = serde_json::json!({
+                                                "total": total,
+                                                "offset": offset,
+                                                "transactions": transactions,
+                                            });
+                                            let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
+                                            let _ = socket.write_all(response.as_bytes());
+                                        }
+                                        Some(Err(err)) => {
+                                            let response = format!("HTTP/1.1 404 NOT FOUND\r\n\r\n{}", err);
+                                            let _ = socket.write_all(response.as_bytes());
+                                        }
+                                        None => {
+                                            let response = "HTTP/1.1 404 NOT FOUND\r\n\r\n";
                                             let _ = socket.write_all(response.as_bytes());
                                         }
                                     }
                                 }
-                                // Convert the callback function to a closure
-                                let closure: Box<dyn FnOnce(Result<(), anyhow::Error>) + Send + 'static> = Box::new(move |result| {            
-                                    callback(result, socket);
-                                });
-                                // Add callback to queue
-                                blockchain.add_transfer(from, to, amount, closure);
+
+                            } else if request_str.starts_with(blocks_prefix) {
+                                // Blocks ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+                                let (path, params) = parse_target(&request_str, blocks_prefix);
+                                let block = match path.as_str() {
+                                    "" | "/" => {
+                                        let from = params.get("from").and_then(|from| from.parse::<u64>().ok()).unwrap_or(0);
+                                        let limit = params.get("limit").and_then(|limit| limit.parse::<usize>().ok()).unwrap_or(BLOCKS_PAGE_SIZE).min(BLOCKS_PAGE_SIZE);
+                                        let blocks = blockchain.get_blocks(from, limit);
+                                        Some(serde_json::to_string(blocks).unwrap())
+                                    }
+                                    "/latest" => blockchain.get_latest_block().map(|block| serde_json::to_string(block).unwrap()),
+                                    path => path
+                                        .strip_prefix('/')
+                                        .and_then(|height| height.parse::<u64>().ok())
+                                        .and_then(|height| blockchain.get_block(height))
+                                        .map(|block| serde_json::to_string(block).unwrap()),
+                                };
+                                match block {
+                                    Some(body) => {
+                                        let response = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                    None => {
+                                        let response = "HTTP/1.1 404 NOT FOUND\r\n\r\nBlock not found";
+                                        let _ = socket.write_all(response.as_bytes());
+                                    }
+                                }
 
                             } else {
-                                let response = format!("HTTP/1.1 404 NOT FOUND\r\n\r\n");
+                                let response = "HTTP/1.1 404 NOT FOUND\r\n\r\n";
                                 let _ = socket.write_all(response.as_bytes());
                             }
                         }
@@ -162,12 +721,8 @@ pub fn server_single_thread() {
             }
         }
 
-        // Process blockchain every 2 seconds
-        let elapsed = last_process_time.elapsed();
-        if elapsed >= Duration::from_secs(1) {
-            blockchain.mint();
-            last_process_time = Instant::now();
-        }
+        // Mint once the slot of the next block is reached
+        blockchain.mint();
     }
 }
 
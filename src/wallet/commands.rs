@@ -0,0 +1,123 @@
+use std::io::IsTerminal;
+use std::process;
+use ed25519_dalek::SigningKey;
+use crate::model::{generate_secret_key, parse_secret_key, public_key_hex};
+use super::Keystore;
+
+/// Passphrase from `B_WALLET_PASSPHRASE`, or asked on the terminal
+fn read_passphrase(new_wallet: bool) -> String {
+    if let Ok(passphrase) = std::env::var("B_WALLET_PASSPHRASE") {
+        return passphrase;
+    }
+    if !new_wallet {
+        return rpassword::prompt_password("Wallet passphrase: ").expect("Failed to read the passphrase");
+    }
+    let passphrase = rpassword::prompt_password("New wallet passphrase: ").expect("Failed to read the passphrase");
+    let confirmation = rpassword::prompt_password("Repeat the passphrase: ").expect("Failed to read the passphrase");
+    if passphrase != confirmation {
+        eprintln!("Passphrases do not match");
+        process::exit(1);
+    }
+    passphrase
+}
+
+/// Secret key to import, asked on the terminal or read from the first line of stdin when it is not one
+fn read_secret_key() -> String {
+    if std::io::stdin().is_terminal() {
+        return rpassword::prompt_password("Secret key: ").expect("Failed to read the secret key");
+    }
+    let mut secret_key = String::new();
+    std::io::stdin().read_line(&mut secret_key).expect("Failed to read the secret key");
+    secret_key.trim().to_string()
+}
+
+fn open_wallet() -> Keystore {
+    let opened = Keystore::default_path().and_then(|path| {
+        let passphrase = read_passphrase(!path.exists());
+        Keystore::open(&path, passphrase)
+    });
+    match opened {
+        Ok(keystore) => keystore,
+        Err(err) => {
+            eprintln!("Error opening the wallet: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+fn save_wallet(keystore: &Keystore) {
+    if let Err(err) = keystore.save() {
+        eprintln!("Error saving the wallet: {}", err);
+        process::exit(1);
+    }
+}
+
+fn add_key(keystore: &mut Keystore, name: &str, secret_key: &SigningKey) {
+    if let Err(err) = keystore.insert(name, secret_key) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+    save_wallet(keystore);
+}
+
+fn get_key(keystore: &Keystore, name: &str) -> SigningKey {
+    match keystore.get(name) {
+        Ok(secret_key) => secret_key,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn wallet_new(name: String) {
+    let mut keystore = open_wallet();
+    let secret_key = generate_secret_key();
+    add_key(&mut keystore, &name, &secret_key);
+    println!("{} {}", name, public_key_hex(&secret_key));
+}
+
+pub fn wallet_list() {
+    let keystore = open_wallet();
+    for name in keystore.names() {
+        println!("{} {}", name, public_key_hex(&get_key(&keystore, name)));
+    }
+}
+
+pub fn wallet_import(name: String) {
+    let secret_key = match parse_secret_key(&read_secret_key()) {
+        Ok(secret_key) => secret_key,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    let mut keystore = open_wallet();
+    add_key(&mut keystore, &name, &secret_key);
+    println!("{} {}", name, public_key_hex(&secret_key));
+}
+
+pub fn wallet_export(name: String) {
+    let keystore = open_wallet();
+    println!("{}", hex::encode(get_key(&keystore, &name).to_bytes()));
+}
+
+pub fn wallet_show(name: String) {
+    let keystore = open_wallet();
+    println!("{} {}", name, public_key_hex(&get_key(&keystore, &name)));
+}
+
+/// Secret key named `name` in the wallet, to sign a transaction with
+pub fn signing_key(name: &str) -> SigningKey {
+    get_key(&open_wallet(), name)
+}
+
+/// Public key of the wallet key named `name`, generating and saving the key if the wallet does not have it yet
+pub fn account_public_key(name: &str) -> String {
+    let mut keystore = open_wallet();
+    if !keystore.contains(name) {
+        add_key(&mut keystore, name, &generate_secret_key());
+        println!("Generated key {} in the wallet", name);
+    }
+    public_key_hex(&get_key(&keystore, name))
+}
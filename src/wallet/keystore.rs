@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ed25519_dalek::SigningKey;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use crate::model::parse_secret_key;
+
+const WALLET_FILE: &str = "wallet.json";
+const LOCK_EXTENSION: &str = "json.lock";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Layout of the wallet file: the named secret keys, encrypted with a key derived from the passphrase
+#[derive(Serialize, Deserialize)]
+struct EncryptedWallet {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Named Ed25519 secret keys kept in a passphrase encrypted file.
+///
+/// An open keystore holds an exclusive lock on the wallet until it is dropped, so that two commands
+/// cannot both read the file and then each save their own changes over the other's.
+pub struct Keystore {
+    path: PathBuf,
+    passphrase: String,
+    keys: BTreeMap<String, String>,
+    _lock: File,
+}
+
+impl Keystore {
+    /// `wallet.json` in the `b` directory of the user's config dir, unless `B_WALLET_DIR` points elsewhere
+    pub fn default_path() -> Result<PathBuf> {
+        let dir = match std::env::var_os("B_WALLET_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => dirs::config_dir()
+                .ok_or_else(|| anyhow::anyhow!("Cannot find the user's config directory"))?
+                .join("b"),
+        };
+        Ok(dir.join(WALLET_FILE))
+    }
+
+    /// Lock and decrypt the wallet at `path`, waiting for other commands using it to finish; a missing file is an empty wallet
+    pub fn open(path: &Path, passphrase: String) -> Result<Keystore> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // The wallet file itself is replaced on save, so the lock lives in a file next to it
+        let lock = fs::OpenOptions::new().write(true).create(true).truncate(false).open(path.with_extension(LOCK_EXTENSION))?;
+        lock.lock()?;
+        let keys = if path.exists() {
+            let wallet: EncryptedWallet = serde_json::from_slice(&fs::read(path)?)
+                .map_err(|err| anyhow::anyhow!("{}: invalid wallet file: {}", path.display(), err))?;
+            let salt = hex::decode(&wallet.salt)?;
+            let nonce = hex::decode(&wallet.nonce)?;
+            if nonce.len() != NONCE_LEN {
+                return Err(anyhow::anyhow!("{}: invalid wallet file", path.display()));
+            }
+            let plaintext = cipher(&passphrase, &salt)?
+                .decrypt(Nonce::from_slice(&nonce), hex::decode(&wallet.ciphertext)?.as_slice())
+                .map_err(|_| anyhow::anyhow!("Wrong passphrase or corrupted wallet"))?;
+            serde_json::from_slice(&plaintext)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Keystore {
+            path: path.to_path_buf(),
+            passphrase,
+            keys,
+            _lock: lock,
+        })
+    }
+
+    /// Encrypt the wallet with a fresh salt and nonce and replace the file
+    pub fn save(&self) -> Result<()> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = cipher(&self.passphrase, &salt)?
+            .encrypt(Nonce::from_slice(&nonce), serde_json::to_vec(&self.keys)?.as_slice())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt the wallet"))?;
+        let wallet = EncryptedWallet {
+            version: 1,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+
+        // Named after the process and a random suffix so that no other writer can be using it
+        let mut suffix = [0u8; 8];
+        OsRng.fill_bytes(&mut suffix);
+        let temporary = self.path.with_extension(format!("json.{}.{}.tmp", std::process::id(), hex::encode(suffix)));
+        write_private(&temporary, &serde_json::to_vec_pretty(&wallet)?)?;
+        if let Err(err) = fs::rename(&temporary, &self.path) {
+            let _ = fs::remove_file(&temporary);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.keys.contains_key(name)
+    }
+
+    pub fn insert(&mut self, name: &str, secret_key: &SigningKey) -> Result<()> {
+        if self.contains(name) {
+            return Err(anyhow::anyhow!("Key {} already exists", name));
+        }
+        self.keys.insert(name.to_string(), hex::encode(secret_key.to_bytes()));
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<SigningKey> {
+        match self.keys.get(name) {
+            Some(secret_key) => parse_secret_key(secret_key),
+            None => Err(anyhow::anyhow!("No key named {} in the wallet", name)),
+        }
+    }
+
+    /// Key names in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.keys.keys()
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| anyhow::anyhow!("Failed to derive the wallet key: {}", err))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Write a new file only the current user can read
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    use std::io::Write;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}